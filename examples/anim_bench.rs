@@ -0,0 +1,49 @@
+use rl2025::*;
+use std::time::Instant;
+
+const ITERATIONS: u32 = 200;
+const FRAME: Seconds = 1. / 60.;
+const MOVE_DURATION: Seconds = 0.2;
+
+// times AnimationQueue ticks through a busy monster turn
+fn main() {
+  for enemies in [50, 100, 200] {
+    let mut frames = 0;
+    let mut ticks = 0;
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+      let mut queue = monster_turn(enemies);
+      frames = 0;
+      while !queue.is_empty() {
+        queue.advance(FRAME);
+        frames += 1;
+      }
+      ticks += frames;
+    }
+    let per = start.elapsed() / ticks;
+    println!("{:>3} enemies: {:>3} frames to drain, {:>8.1?} per tick", enemies, frames, per);
+  }
+}
+
+// roughly what main queues for a monster turn: every enemy steps one space,
+// reserving where it was, where it goes and itself, then the hud catches up
+fn monster_turn(enemies: usize) -> AnimationQueue {
+  let mut queue = AnimationQueue::starting_at(0.);
+  queue.append(|t| t.elapsed < MOVE_DURATION)
+    .reserve([IVec { x: 0, y: 0 }, IVec { x: 0, y: 1 }])
+    .reserve(PLAYER_UNIT_ID);
+  queue.sync_positions();
+  for i in 0..enemies {
+    // spread out so no two enemies really share a space
+    let from = IVec { x: (3 * i % 48) as i16 + 1, y: (3 * (i / 16) % 48) as i16 + 2 };
+    let to = from + IVec { x: 1, y: 0 };
+    let id = 1000 + i as UnitId;
+    queue.append(|t| t.elapsed < MOVE_DURATION)
+      .reserve([from, to])
+      .reserve(id);
+    queue.append(|_| false).reserve(id).chain();
+  }
+  queue.sync_positions();
+  queue.append(|t| t.elapsed < 0.1).chain();
+  queue
+}
@@ -0,0 +1,45 @@
+use rl2025::*;
+use std::time::Instant;
+
+const ITERATIONS: u32 = 200;
+
+// times DijkstraMap fills on a board shaped like a game in progress
+fn main() {
+  let mut rng = Rng::new(0x2025, 0x7);
+  let mut board: Buffer2D<Tile> = Buffer2D::new(Tile::default(), BOARD_RECT);
+  for p in BOARD_RECT.iter() {
+    // about a third of the board is still void
+    if !rng.next_u32().is_multiple_of(3) {
+      board[p] = tiles::generate(&mut rng);
+    }
+  }
+  let center = IVec { x: 25, y: 25 };
+  board[center] = tiles::generate(&mut rng);
+  let sources: Vec<Position> = BOARD_RECT.iter()
+    .filter(|&p| board[p] != Tile::default() && rng.next_u32().is_multiple_of(40))
+    .collect();
+
+  let mut dmap = DijkstraMap::new(BOARD_RECT);
+  bench("single source, walk cost", || {
+    dmap.fill([(center, 0)], walk_cost(&board));
+  });
+  bench(&format!("{} sources, walk cost", sources.len()), || {
+    dmap.fill(sources.iter().map(|&p| (p, 0)), walk_cost(&board));
+  });
+  bench("single source, terrain cost", || {
+    dmap.fill([(center, 0)], terrain_cost(&board));
+  });
+  dmap.fill(sources.iter().map(|&p| (p, 0)), walk_cost(&board));
+  bench("flee map", || {
+    dmap.flee(1.2, walk_cost(&board));
+  });
+}
+
+fn bench(name: &str, mut f: impl FnMut()) {
+  let start = Instant::now();
+  for _ in 0..ITERATIONS {
+    f();
+  }
+  let per = start.elapsed() / ITERATIONS;
+  println!("{:<32} {:>8.1?} per fill", name, per);
+}
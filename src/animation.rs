@@ -1,22 +1,121 @@
 use std::collections::VecDeque;
 use crate::*;
 
-#[derive(Clone, Copy)]
+// the positions and unit ids one side of a lock claims
+// the masks are a fast path: sets whose masks don't meet share nothing,
+// and only when they do are the exact lists compared
+#[derive(Clone, Default, Debug)]
+pub struct LockSet {
+  position_mask: u64,
+  id_mask: u64,
+  // wrapped onto the board, so equivalent positions compare equal
+  positions: Vec<Position>,
+  ids: Vec<UnitId>,
+  all_positions: bool,
+  all_ids: bool,
+}
+
+impl LockSet {
+  pub fn is_empty(&self) -> bool {
+    !self.all_positions && !self.all_ids
+      && self.positions.is_empty() && self.ids.is_empty()
+  }
+
+  pub fn insert_position(&mut self, p: Position) {
+    let p = BOARD_RECT.wrap(p);
+    if self.positions.contains(&p) { return; }
+    self.position_mask |= pack_location(p);
+    self.positions.push(p);
+  }
+
+  pub fn insert_id(&mut self, id: UnitId) {
+    if self.ids.contains(&id) { return; }
+    self.id_mask |= pack_id(id);
+    self.ids.push(id);
+  }
+
+  pub fn positions(&self) -> &[Position] {
+    &self.positions
+  }
+
+  pub fn ids(&self) -> &[UnitId] {
+    &self.ids
+  }
+
+  // claims every position (or id), even ones never named
+  pub fn covers_all_positions(&self) -> bool {
+    self.all_positions
+  }
+
+  pub fn covers_all_ids(&self) -> bool {
+    self.all_ids
+  }
+
+  // a readable list of what the set claims, for the debug overlay
+  pub fn describe(&self) -> String {
+    let mut parts: Vec<String> = vec![];
+    if self.all_positions { parts.push("all positions".to_string()); }
+    if self.all_ids { parts.push("all units".to_string()); }
+    if !self.ids.is_empty() {
+      let ids: Vec<String> = self.ids.iter().map(|id| id.to_string()).collect();
+      parts.push(format!("units {}", ids.join(" ")));
+    }
+    if !self.positions.is_empty() {
+      let ps: Vec<String> = self.positions.iter().map(|p| format!("({},{})", p.x, p.y)).collect();
+      parts.push(ps.join(" "));
+    }
+    if parts.is_empty() { return "-".to_string(); }
+    parts.join(", ")
+  }
+
+  pub fn intersects(&self, other: &LockSet) -> bool {
+    self.positions_intersect(other) || self.ids_intersect(other)
+  }
+
+  fn positions_intersect(&self, other: &LockSet) -> bool {
+    let (a, b) = (self, other);
+    if a.all_positions { return b.all_positions || !b.positions.is_empty(); }
+    if b.all_positions { return !a.positions.is_empty(); }
+    if a.position_mask & b.position_mask == 0 { return false; }
+    a.positions.iter()
+      .filter(|&&p| b.position_mask & pack_location(p) != 0)
+      .any(|p| b.positions.contains(p))
+  }
+
+  fn ids_intersect(&self, other: &LockSet) -> bool {
+    let (a, b) = (self, other);
+    if a.all_ids { return b.all_ids || !b.ids.is_empty(); }
+    if b.all_ids { return !a.ids.is_empty(); }
+    if a.id_mask & b.id_mask == 0 { return false; }
+    a.ids.iter()
+      .filter(|&&id| b.id_mask & pack_id(id) != 0)
+      .any(|id| b.ids.contains(id))
+  }
+
+  // duplicates are kept, checking for them would cost more than they do
+  pub fn extend(&mut self, other: &LockSet) {
+    self.all_positions |= other.all_positions;
+    self.all_ids |= other.all_ids;
+    self.position_mask |= other.position_mask;
+    self.id_mask |= other.id_mask;
+    self.positions.extend_from_slice(&other.positions);
+    self.ids.extend_from_slice(&other.ids);
+  }
+}
+
+#[derive(Clone, Default, Debug)]
 pub struct AnimLock{
-  require: u128,
-  reserve: u128,
+  pub require: LockSet,
+  pub reserve: LockSet,
 }
 
 pub trait Lock {
-  fn to_lock(self) -> u128;
+  fn add_to(self, set: &mut LockSet);
 }
 
 impl AnimLock {
   pub fn empty() -> Self {
-    AnimLock {
-      require: 0,
-      reserve: 0,
-    }
+    AnimLock::default()
   }
 
 
@@ -29,145 +128,119 @@ impl AnimLock {
   }
 
   // is a pair of locks claiming the same resource?
-  pub fn overlaps(&self, other: Self) -> bool {
-    (self.require & other.reserve > 0)
-      || (self.reserve & other.require > 0)
+  pub fn overlaps(&self, other: &Self) -> bool {
+    self.require.intersects(&other.reserve)
+      || self.reserve.intersects(&other.require)
   }
 
-  pub fn merge(&self, other: Self) -> Self {
-    AnimLock {
-      require: self.require | other.require,
-      reserve: self.reserve | other.reserve,
-    }
+  pub fn merge(&self, other: &Self) -> Self {
+    let mut x = self.clone();
+    x |= other;
+    x
   }
 
   pub fn full() -> Self {
+    let all = LockSet { all_positions: true, all_ids: true, ..LockSet::default() };
     AnimLock {
-      require: u128::MAX,
-      reserve: u128::MAX,
+      require: all.clone(),
+      reserve: all,
     }
   }
 
   pub fn full_positions() -> Self {
+    let all = LockSet { all_positions: true, ..LockSet::default() };
     AnimLock {
-      require: u64::MAX.into(),
-      reserve: u64::MAX.into(),
+      require: all.clone(),
+      reserve: all,
     }
   }
 
   pub fn require(mut self, req: impl Lock) -> Self {
-    self.require |= req.to_lock(); //Self::pack_locations(BOUNDS, locs);
+    req.add_to(&mut self.require);
     self
   }
 
-  // pub fn require_id(mut self, id: u64) -> Self {
-  //   self.require |= Self::pack_id(id);
-  //   self
-  // }
-
   pub fn reserve(mut self, reserve: impl Lock) -> Self {
-    let l = reserve.to_lock();
-    self.require |= l;
-    self.reserve |= l;
+    let mut l = LockSet::default();
+    reserve.add_to(&mut l);
+    self.require.extend(&l);
+    self.reserve.extend(&l);
     self
   }
-
-  // pub fn reserve_id(mut self, id: u64) -> Self {
-  //   self.require |= Self::pack_id(id);
-  //   self.reserve |= Self::pack_id(id);
-  //   self
-  // }
-
-
-  // fn from_locations(rect: IRect, locs: &[IVec]) -> Self {
-  //   let x = Self::pack_locations(rect, locs);
-  //   AnimLock{
-  //     require: x,
-  //     reserve: x,
-  //   }
-  // }
 }
 
-pub fn pack_location(loc: IVec) -> u128 {
-  let mut x = 0;
-  let px = (loc.x) as u32;
-  let py = (loc.y) as u32;
-  let bit = morton_curve(px,py) % 64;
-  x = x | (1 << bit);
-  x
+// the fast path masks: one bit per position or id, shared by many
+pub fn pack_location(loc: IVec) -> u64 {
+  let loc = BOARD_RECT.wrap(loc);
+  let bit = morton_curve(loc.x as u32, loc.y as u32) % 64;
+  1 << bit
 }
 
-pub fn pack_id(id: impl Into<u64>) -> u128 {
-  1 << ((id.into() % 64) + 64)
+pub fn pack_id(id: impl Into<u64>) -> u64 {
+  1 << (id.into() % 64)
 }
 
 
 impl Lock for u64 {
-  fn to_lock(self) -> u128 {
-    pack_id(self)
+  fn add_to(self, set: &mut LockSet) {
+    set.insert_id(self)
   }
 }
 
 impl Lock for &u64 {
-  fn to_lock(self) -> u128 {
-    pack_id(*self)
+  fn add_to(self, set: &mut LockSet) {
+    set.insert_id(*self)
   }
 }
 
 impl Lock for IVec {
-  fn to_lock(self) -> u128 {
-    pack_location(self)
+  fn add_to(self, set: &mut LockSet) {
+    set.insert_position(self)
   }
 }
 impl Lock for &IVec {
-  fn to_lock(self) -> u128 {
-    pack_location(*self)
+  fn add_to(self, set: &mut LockSet) {
+    set.insert_position(*self)
   }
 }
 
 impl<T:Lock + Copy> Lock for &Vec<T> {
-  fn to_lock(self) -> u128 {
-    let mut it = 0;
-    for x in self.iter() {
-      it |= x.to_lock()
+  fn add_to(self, set: &mut LockSet) {
+    for &x in self.iter() {
+      x.add_to(set)
     }
-    it
   }
 }
 impl<T:Lock + Copy> Lock for &[T] {
-  fn to_lock(self) -> u128 {
-    let mut it = 0;
-    for x in self {
-      it |= x.to_lock()
+  fn add_to(self, set: &mut LockSet) {
+    for &x in self {
+      x.add_to(set)
     }
-    it
   }
 }
 impl<T:Lock + Copy, const N: usize> Lock for &[T;N] {
-  fn to_lock(self) -> u128 {
-    let mut it = 0;
-    for x in self {
-      it |= x.to_lock()
+  fn add_to(self, set: &mut LockSet) {
+    for &x in self {
+      x.add_to(set)
     }
-    it
   }
 }
 impl<T:Lock + Copy, const N: usize> Lock for [T;N] {
-  fn to_lock(self) -> u128 {
-    let mut it = 0;
+  fn add_to(self, set: &mut LockSet) {
     for x in self {
-      it |= x.to_lock()
+      x.add_to(set)
     }
-    it
   }
 }
 
-impl std::ops::BitOrAssign<AnimLock> for AnimLock {
-  fn bitor_assign(&mut self, other: AnimLock) {
-    *self = self.merge(other)
+impl std::ops::BitOrAssign<&AnimLock> for AnimLock {
+  fn bitor_assign(&mut self, other: &AnimLock) {
+    self.require.extend(&other.require);
+    self.reserve.extend(&other.reserve);
   }
 }
 
+
 //impl From<Event> for AnimLock {
 //  fn from(it: Event) -> Self {
 //    match it {
@@ -209,13 +282,17 @@ impl <T1,T2> From<(T1,T2)> for AnimLock where
 {
   fn from(xs: (T1,T2)) -> Self {
     let mut x = AnimLock::empty();
-    x = x.merge(xs.0.into());
-    x = x.merge(xs.1.into());
+    x |= &xs.0.into();
+    x |= &xs.1.into();
     x
   }
 }
 
 
+// finish_all pretends this much time has passed, which is past the
+// duration of any animation in the game
+const FINISH_ELAPSED: Seconds = 1e6;
+
 pub struct AnimationQueue {
   timestamp: Seconds,
   animations: VecDeque<Animation>,
@@ -227,23 +304,47 @@ pub fn empty_animation(_t: Time) -> bool {
 
 impl AnimationQueue {
   pub fn new() -> Self {
+    Self::starting_at(get_time())
+  }
+
+  // for driving the queue without a window, with advance
+  pub fn starting_at(timestamp: Seconds) -> Self {
     AnimationQueue {
-      timestamp: get_time(),
+      timestamp,
       animations: VecDeque::new(),
     }
   }
 
+  // drops every animation without running it, including any that update
+  // the hud, so prefer finish_all unless the hud is about to be thrown away
   pub fn clear(&mut self) {
     self.animations.clear();
   }
 
+  // runs every queued animation to completion right away
+  // animations start in the same order tick would start them, and each
+  // one sees enough elapsed time to reach its end
+  pub fn finish_all(&mut self) {
+    // every pass finishes at least the first animation, unless it never ends
+    let mut passes = self.animations.len() + 1;
+    while !self.animations.is_empty() {
+      if passes == 0 {
+        warn!("{} animations never finished, dropping them", self.animations.len());
+        self.animations.clear();
+        break;
+      }
+      passes -= 1;
+      self.advance(FINISH_ELAPSED);
+    }
+  }
+
   pub fn sync(&mut self) {
-    let a = self.append(empty_animation);
+    let a = self.append(empty_animation).label("sync");
     a.lock = AnimLock::full();
   }
 
   pub fn sync_positions(&mut self) -> &mut Animation {
-    let a = self.append(empty_animation);
+    let a = self.append(empty_animation).label("sync positions");
     a.lock = AnimLock::full_positions();
     a
   }
@@ -259,11 +360,16 @@ impl AnimationQueue {
       elapsed: 0.,
       f: Box::new(func),
       state: AnimationState::Waiting,
-      speed: 1.
+      speed: 1.,
+      label: None,
     });
     self.animations.back_mut().unwrap()
   }
 
+  pub fn append_tween(&mut self, mut tween: Tween) -> &mut Animation {
+    self.append(move |time| tween.run(time.elapsed))
+  }
+
   pub fn append_empty(&mut self, duration: Seconds) -> &mut Animation {
     self.append(move |time| { time.elapsed < duration })
   }
@@ -272,6 +378,40 @@ impl AnimationQueue {
     self.animations.len()
   }
 
+  pub fn is_empty(&self) -> bool {
+    self.animations.is_empty()
+  }
+
+  // a snapshot of every queued animation, for the debug overlay
+  // a waiting animation is blocked by the first earlier unfinished one
+  // whose lock overlaps its own, or failing that, for a chained one,
+  // by the animation right before it
+  pub fn inspect(&self) -> Vec<AnimationInfo> {
+    let mut infos: Vec<AnimationInfo> = vec![];
+    for (i, a) in self.animations.iter().enumerate() {
+      let waiting = a.state == AnimationState::Waiting || a.state == AnimationState::Chain;
+      let mut blocked_by = None;
+      if waiting {
+        blocked_by = (0..i).find(|&j| {
+          let b = &self.animations[j];
+          b.state != AnimationState::Finished && b.lock.overlaps(&a.lock)
+        });
+        if blocked_by.is_none() && a.state == AnimationState::Chain && i > 0
+          && self.animations[i - 1].state != AnimationState::Finished {
+          blocked_by = Some(i - 1);
+        }
+      }
+      infos.push(AnimationInfo {
+        label: a.label,
+        state: a.state,
+        elapsed: a.elapsed,
+        lock: a.lock.clone(),
+        blocked_by,
+      });
+    }
+    infos
+  }
+
   pub fn hurry(&mut self, c: f64) {
     for a in self.animations.iter_mut() {
       a.speed *= c;
@@ -282,18 +422,22 @@ impl AnimationQueue {
     let now = get_time();
     let delta = now - self.timestamp;
     self.timestamp = now;
+    self.advance(delta);
+  }
 
+  // advances the queue by delta seconds
+  pub fn advance(&mut self, delta: Seconds) {
     let mut lock: AnimLock = AnimLock::empty();
     let mut can_chain = true;
 
     let mut i = 0;
     while i < self.animations.len() {
       let a = &mut self.animations[i];
-      a.try_wake(lock, can_chain);
+      a.try_wake(&lock, can_chain);
       a.tick(delta);
       let is_finished = a.state == AnimationState::Finished;
       can_chain = is_finished;
-      if !is_finished { lock |= a.lock; }
+      if !is_finished { lock |= &a.lock; }
       if is_finished && i == 0 {
         self.animations.pop_front();
       } else {
@@ -322,6 +466,19 @@ pub struct Animation {
   elapsed: Seconds,
   state: AnimationState,
   speed: f64,
+  // names the animation in the debug overlay
+  label: Option<&'static str>,
+}
+
+// what the debug overlay shows for one queued animation
+#[derive(Clone, Debug)]
+pub struct AnimationInfo {
+  pub label: Option<&'static str>,
+  pub state: AnimationState,
+  pub elapsed: Seconds,
+  pub lock: AnimLock,
+  // the index of the earlier animation holding this one back
+  pub blocked_by: Option<usize>,
 }
 
 impl Animation {
@@ -336,12 +493,17 @@ impl Animation {
 
   // Non-exclusive lock
   pub fn require(&mut self, locs: impl Lock) -> &mut Self {
-    self.lock = self.lock.require(locs); self
+    locs.add_to(&mut self.lock.require); self
   }
 
   // Exclusive lock
   pub fn reserve(&mut self, locs: impl Lock) -> &mut Self  {
-    self.lock = self.lock.reserve(locs); self
+    self.lock = std::mem::take(&mut self.lock).reserve(locs); self
+  }
+
+  pub fn label(&mut self, label: &'static str) -> &mut Self {
+    self.label = Some(label);
+    self
   }
 
   // Don't start until prior animation finishes
@@ -350,7 +512,7 @@ impl Animation {
     self
   }
 
-  pub fn try_wake(&mut self, conflicts: AnimLock, has_chain: bool) {
+  pub fn try_wake(&mut self, conflicts: &AnimLock, has_chain: bool) {
     match self.state {
       AnimationState::Waiting => {
         if !self.lock.overlaps(conflicts) {
@@ -368,8 +530,8 @@ impl Animation {
 
 }
 
-#[derive(Clone,Copy,Eq,PartialEq)]
-enum AnimationState {
+#[derive(Clone,Copy,Eq,PartialEq,Debug)]
+pub enum AnimationState {
   Waiting,
   Chain,
   Active,
@@ -11,6 +11,8 @@ pub const LOAD_ME: &[&'static str] = &[
   "nme1.png",
   "nme2.png",
   "nme3.png",
+  "nme4.png",
+  "nme5.png",
   "nme_unknown.png",
   "xp.png",
   "time.png",
@@ -26,6 +28,8 @@ pub const LOAD_ME: &[&'static str] = &[
   "rothint.png",
   "path.png",
   "flag.png",
+  "bomb.png",
+  "eye.png",
 ];
 
 pub const SOUNDS_TO_LOAD: &[&'static str] = &[
@@ -86,6 +90,8 @@ pub const SCROLL: Img = def("scroll.png");
 pub const HEART: Img = def("heart.png");
 pub const HINT: Img = def("rothint.png");
 pub const FLAG: Img = def("flag.png");
+pub const BOMB: Img = def("bomb.png");
+pub const EYE: Img = def("eye.png");
 
 
 pub const fn path_img(dir: Dir4, arrow: bool) -> Img {
@@ -113,6 +119,8 @@ pub const fn enemy_img(nme: EnemyType, alarmed: bool) -> Img {
     EnemyType::Clyde  => "nme1.png",
     EnemyType::Blinky   => "nme2.png",
     EnemyType::Pinky => "nme3.png",
+    EnemyType::Inky => "nme4.png",
+    EnemyType::Sue => "nme5.png",
     EnemyType::GhostWitch  => "boss.png",
   };
 
@@ -128,6 +136,11 @@ pub const fn enemy_img(nme: EnemyType, alarmed: bool) -> Img {
 pub const fn prize_img(prize: Prize) -> Img {
   match prize {
     Prize::Heal => HEART,
+    Prize::Tiles => TILE,
+    Prize::Reroll => HINT,
+    Prize::Freeze => TIME,
+    Prize::Reveal => EYE,
+    Prize::Bomb => BOMB,
   }
 }
 
@@ -0,0 +1,412 @@
+use crate::*;
+
+pub const FOREST_ESCAPE_CHANCE: u64 = 250;
+// how far enemies can see the player
+pub const SIGHT_RADIUS: u8 = 8;
+// how far ahead of the player inkies aim
+const INKY_LEAD: i16 = 2;
+// how far sues can shoot
+pub const SHOT_RANGE: i16 = 6;
+// how strongly pinkies prefer open escape routes
+const SHY_FLEE_COEFFICIENT: f32 = 1.2;
+
+// a read-only look at the parts of the game enemies care about
+pub struct EnemyView<'a> {
+  pub board: &'a Buffer2D<Tile>,
+  pub enemies: &'a WrapMap<Enemy>,
+  pub quests: &'a WrapMap<Quest>,
+  pub prizes: &'a WrapMap<Prize>,
+  pub player_pos: Position,
+  // direction of the player's last step
+  pub player_facing: Dir4,
+  // distance to the player, walking on tiles
+  pub player_dmap: &'a DMap,
+  // spaces in line of sight of the player
+  pub player_fov: &'a WrapSet,
+  // where each enemy last saw the player
+  pub last_seen: &'a Map<UnitId, Position>,
+  // chance (1/10 percent) per direction to step out of a forest
+  pub forest_escape_chance: u64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Awareness {
+  Sees,
+  Remembers(Position),
+  Unaware,
+}
+
+impl<'a> EnemyView<'a> {
+  pub fn awareness(&self, pos: Position) -> Awareness {
+    if self.player_fov.contains(pos) {
+      return Awareness::Sees;
+    }
+    let Some(nme) = self.enemies.get(pos) else { return Awareness::Unaware; };
+    match self.last_seen.get(&nme.id) {
+      Some(&spot) => Awareness::Remembers(spot),
+      None => Awareness::Unaware,
+    }
+  }
+}
+
+pub trait EnemyBehavior {
+  // runs once per monster turn, before any enemy moves
+  // behaviors that need their own dmaps build them here
+  fn prepare(&mut self, _view: &EnemyView) {}
+
+  // the moves the enemy at pos is choosing between
+  fn candidates(&self, view: &EnemyView, pos: Position, rng: &mut Rng) -> Vec<Position>;
+
+  // where the enemy at pos wants to go
+  // returning pos (or None) means it stays put
+  fn choose_move(&self, view: &EnemyView, pos: Position, rng: &mut Rng) -> Option<Position> {
+    let candidates = self.candidates(view, pos, rng);
+    select_candidate(view, candidates, rng)
+  }
+}
+
+#[derive(Default)]
+pub struct Behaviors {
+  table: Map<EnemyType, Box<dyn EnemyBehavior>>,
+}
+
+impl Behaviors {
+  pub fn new() -> Self {
+    Self { table: Map::new() }
+  }
+
+  // the standard cast of ghosts
+  pub fn standard() -> Self {
+    let mut b = Self::new();
+    b.register(EnemyType::Clyde, Wander);
+    b.register(EnemyType::Blinky, Chase);
+    b.register(EnemyType::Pinky, Shy::new());
+    b.register(EnemyType::Inky, Flank::new());
+    b.register(EnemyType::Sue, Snipe);
+    b.register(EnemyType::GhostWitch, Stationary);
+    b
+  }
+
+  pub fn register(&mut self, t: EnemyType, behavior: impl EnemyBehavior + 'static) {
+    self.table.insert(t, Box::new(behavior));
+  }
+
+  pub fn prepare(&mut self, view: &EnemyView) {
+    for (_, behavior) in self.table.iter_mut() {
+      behavior.prepare(view);
+    }
+  }
+
+  pub fn choose_move(&self, view: &EnemyView, pos: Position, rng: &mut Rng) -> Option<Position> {
+    let nme = view.enemies.get(pos)?;
+    let behavior = self.table.get(&nme.t)?;
+    behavior.choose_move(view, pos, rng)
+  }
+
+  // the spaces the enemy at pos might actually end up moving to
+  pub fn candidates(&self, view: &EnemyView, pos: Position, rng: &mut Rng) -> Vec<Position> {
+    let Some(nme) = view.enemies.get(pos) else { return vec![]; };
+    let Some(behavior) = self.table.get(&nme.t) else { return vec![]; };
+    valid_candidates(view, behavior.candidates(view, pos, rng))
+  }
+}
+
+// Clyde: moves randomly
+pub struct Wander;
+impl EnemyBehavior for Wander {
+  fn candidates(&self, view: &EnemyView, pos: Position, rng: &mut Rng) -> Vec<Position> {
+    candidate_moves(view, pos, rng)
+  }
+}
+
+// Blinky: chases the player while it can see them
+// otherwise it checks where they were last seen, then gives up and wanders
+pub struct Chase;
+impl EnemyBehavior for Chase {
+  fn candidates(&self, view: &EnemyView, pos: Position, rng: &mut Rng) -> Vec<Position> {
+    let candidates = candidate_moves(view, pos, rng);
+    match view.awareness(pos) {
+      Awareness::Sees => keep_min(candidates, view.player_dmap),
+      Awareness::Remembers(spot) => keep_closest(candidates, spot),
+      Awareness::Unaware => candidates,
+    }
+  }
+}
+
+// Pinky: avoids other enemies
+pub struct Shy {
+  nearest_enemy_dmap: DMap,
+  flee_dmap: DMap,
+}
+impl Shy {
+  pub fn new() -> Self {
+    Self {
+      nearest_enemy_dmap: DijkstraMap::new(BOARD_RECT),
+      flee_dmap: DijkstraMap::new(BOARD_RECT),
+    }
+  }
+}
+impl Default for Shy {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+impl EnemyBehavior for Shy {
+  fn prepare(&mut self, view: &EnemyView) {
+    let mut sources = Vec::new();
+    for (pos, nme) in view.enemies.iter() {
+      if nme.t == EnemyType::Pinky {
+        // pinkies shouldn't hide from each other
+        continue;
+      }
+      sources.push(*pos);
+    }
+    fill_dmap(&mut self.nearest_enemy_dmap, view.board, sources);
+    self.flee_dmap = self.nearest_enemy_dmap.flee(SHY_FLEE_COEFFICIENT, walk_cost(view.board));
+  }
+
+  fn candidates(&self, view: &EnemyView, pos: Position, rng: &mut Rng) -> Vec<Position> {
+    let candidates = candidate_moves(view, pos, rng);
+    keep_min(candidates, &self.flee_dmap)
+  }
+}
+
+// Inky: tries to cut the player off
+pub struct Flank {
+  target_dmap: DMap,
+}
+impl Flank {
+  pub fn new() -> Self {
+    Self { target_dmap: DijkstraMap::new(BOARD_RECT) }
+  }
+
+  // the spot ahead of the player, reflected away from the nearest blinky
+  pub fn target(view: &EnemyView) -> Position {
+    let ahead = view.player_pos + INKY_LEAD * IVec::from(view.player_facing);
+    let nearest_blinky = view.enemies.iter()
+      .filter(|(_, nme)| nme.t == EnemyType::Blinky)
+      .map(|(&pos, _)| torus_offset(BOARD_RECT, ahead - pos))
+      .min_by_key(|offset| offset.abs().x + offset.abs().y);
+    match nearest_blinky {
+      Some(offset) => ahead + offset,
+      None => ahead,
+    }
+  }
+}
+impl Default for Flank {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+impl EnemyBehavior for Flank {
+  fn prepare(&mut self, view: &EnemyView) {
+    fill_dmap(&mut self.target_dmap, view.board, vec![Self::target(view)]);
+  }
+
+  fn candidates(&self, view: &EnemyView, pos: Position, rng: &mut Rng) -> Vec<Position> {
+    let candidates = candidate_moves(view, pos, rng);
+    match view.awareness(pos) {
+      Awareness::Sees => keep_min(candidates, &self.target_dmap),
+      Awareness::Remembers(spot) => keep_closest(candidates, spot),
+      Awareness::Unaware => candidates,
+    }
+  }
+}
+
+// Sue: lines up a shot with the player instead of closing in
+// the shooting itself is handled by the monster turn (see Aim)
+pub struct Snipe;
+impl EnemyBehavior for Snipe {
+  fn candidates(&self, view: &EnemyView, pos: Position, rng: &mut Rng) -> Vec<Position> {
+    let candidates = candidate_moves(view, pos, rng);
+    match view.awareness(pos) {
+      Awareness::Sees => {
+        let lined_up: Vec<Position> = candidates.iter()
+          .copied()
+          .filter(|&c| line_of_fire(view.board, c, view.player_pos).is_some())
+          .collect();
+        if !lined_up.is_empty() { lined_up } else { keep_min(candidates, view.player_dmap) }
+      }
+      Awareness::Remembers(spot) => keep_closest(candidates, spot),
+      Awareness::Unaware => candidates,
+    }
+  }
+}
+
+// a shot an enemy has announced and will take on its next turn
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Aim {
+  pub dir: Dir4,
+  // the player has had a turn to get out of the way
+  pub ready: bool,
+}
+
+// the spaces a shot passes through, ending at whatever stops it
+pub fn shot_path(board: &Buffer2D<Tile>, from: Position, dir: Dir4) -> Vec<Position> {
+  let rect = IRect {
+    x: from.x - SHOT_RANGE,
+    y: from.y - SHOT_RANGE,
+    width: 2 * SHOT_RANGE + 1,
+    height: 2 * SHOT_RANGE + 1,
+  };
+  let mut blocked = Buffer2D::new(false, rect);
+  for p in rect.iter() {
+    blocked[p] = board[p].blocks_shots();
+  }
+  projectile_path(from, dir, &blocked)
+}
+
+// which way to shoot from one space to hit another, if a shot would get there
+pub fn line_of_fire(board: &Buffer2D<Tile>, from: Position, to: Position) -> Option<Dir4> {
+  let to = from + torus_offset(BOARD_RECT, to - from);
+  let dir = cardinal_alignment(from, to)?;
+  if shot_path(board, from, dir).contains(&to) { Some(dir) } else { None }
+}
+
+// the boss does not move
+pub struct Stationary;
+impl EnemyBehavior for Stationary {
+  fn candidates(&self, _view: &EnemyView, _pos: Position, _rng: &mut Rng) -> Vec<Position> {
+    vec![]
+  }
+
+  fn choose_move(&self, _view: &EnemyView, pos: Position, _rng: &mut Rng) -> Option<Position> {
+    Some(pos)
+  }
+}
+
+// the places an enemy is allowed to step this turn, including staying put
+// enemies in a forest mostly stick to forest edges
+pub fn candidate_moves(view: &EnemyView, pos: Position, rng: &mut Rng) -> Vec<Position> {
+  // add forest edges to valid set
+  let mut valid: Vec<Dir4> = forest_edges(&pos, view.board);
+  debug!("forest dirs {:?} for {:?}", valid, view.enemies.get(pos));
+  if valid.is_empty() {
+    // no forest edges means anything is a candidate
+    valid = Dir4::list().into();
+  }
+  else {
+    // still have a chance to escape the forest
+    for d in Dir4::list().iter() {
+      if roll_chance(rng, view.forest_escape_chance) {
+        valid.push(*d);
+      }
+    }
+  }
+  debug!("valid dirs {:?} for {:?}", valid, view.enemies.get(pos));
+
+  let mut candidates: Vec<IVec> = Vec::new();
+  for &d in &valid {
+    let target = pos + IVec::from(d);
+    // dont step on me
+    if equivalent(target, view.player_pos) { continue; }
+    // no void
+    if view.board[target] == Tile::default() { continue; }
+    // dont step on quest
+    if view.quests.contains_key(target) { continue; }
+    // dont step on prize
+    if view.prizes.contains_key(target) { continue; }
+    candidates.push(target);
+  }
+  if view.board[pos] != Tile::default() {
+    candidates.push(pos);
+  }
+  candidates
+}
+
+// the candidates that are on a tile and not occupied
+pub fn valid_candidates(view: &EnemyView, mut candidates: Vec<Position>) -> Vec<Position> {
+  let mut valid: Vec<IVec> = Vec::new();
+  for c in candidates.drain(0..) {
+    if view.board[c] != Tile::default() && !view.enemies.contains_key(c) {
+      valid.push(c);
+    }
+  }
+  valid
+}
+
+// picks one of the valid candidates
+pub fn select_candidate(view: &EnemyView, candidates: Vec<Position>, rng: &mut Rng) -> Option<Position> {
+  let valid = valid_candidates(view, candidates);
+  if !valid.is_empty() {
+    Some(valid[rng.next_u32() as usize % valid.len()])
+  }
+  else {
+    None
+  }
+}
+
+pub fn keep_min(mut candidates: Vec<Position>, dmap: &DMap) -> Vec<Position> {
+  let mut min_score: i16 = i16::MAX;
+  for &c in &candidates {
+    min_score = min_score.min(dmap[c]);
+  }
+  candidates.drain(..).filter(|c|{
+    min_score == dmap[*c]
+  }).collect()
+}
+
+pub fn keep_max(mut candidates: Vec<Position>, dmap: &DMap) -> Vec<Position> {
+  let mut max_score: i16 = i16::MIN;
+  for &c in &candidates {
+    max_score = max_score.max(dmap[c]);
+  }
+  candidates.drain(..).filter(|c|{
+    max_score == dmap[*c]
+  }).collect()
+}
+
+// keeps the candidates nearest to a spot, as the crow flies around the torus
+pub fn keep_closest(mut candidates: Vec<Position>, spot: Position) -> Vec<Position> {
+  let distance = |c: Position| {
+    let offset = torus_offset(BOARD_RECT, spot - c).abs();
+    offset.x + offset.y
+  };
+  let min_score = candidates.iter().map(|&c| distance(c)).min().unwrap_or(0);
+  candidates.drain(..).filter(|&c| distance(c) == min_score).collect()
+}
+
+pub fn forest_edges(pos: &Position, board: &Buffer2D<Tile>) -> Vec<Dir4> {
+  // right up left down (matching dir4.index)
+  let mut candidates: Vec<Dir4> = Vec::new();
+  let tile: Tile = board[*pos];
+  for ix in 0..4 {
+    let dir: Dir4 = Dir4::list()[ix];
+    let neighbor: Tile = board[*pos + dir.into()];
+    let edge1 = tile.contents[ix];
+    let edge2 = neighbor.contents[dir.opposite().index()];
+    if edge1 == Terrain::Forest && edge2 == Terrain::Forest {
+      candidates.push(dir);
+    }
+  }
+  candidates
+}
+
+// distance from the nearest source, walking on tiles
+pub fn fill_dmap(dmap: &mut DMap, board: &Buffer2D<Tile>, sources: Vec<Position>) {
+  dmap.fill(sources.into_iter().map(|p| (p, 0)), walk_cost(board));
+}
+
+// every step onto a tile costs the same, void can't be walked on
+pub fn walk_cost(board: &Buffer2D<Tile>) -> impl Fn(Position, Position) -> Option<i16> + '_ {
+  move |_from, to| {
+    if board[to] == Tile::default() { None } else { Some(1) }
+  }
+}
+
+// like walk_cost, but following a road is quick and rivers are slow
+pub fn terrain_cost(board: &Buffer2D<Tile>) -> impl Fn(Position, Position) -> Option<i16> + '_ {
+  move |from, to| {
+    let tile = board[to];
+    if tile == Tile::default() { return None; }
+    let dir = Dir4::try_from(torus_offset(BOARD_RECT, to - from)).ok()?;
+    if board[from].contents[dir.index()] == Terrain::Road
+      && tile.contents[dir.opposite().index()] == Terrain::Road {
+      Some(1)
+    } else if tile.contents[4] == Terrain::River {
+      Some(4)
+    } else {
+      Some(2)
+    }
+  }
+}
@@ -0,0 +1,123 @@
+use crate::*;
+
+// monster turns between boss abilities
+pub const BOSS_COOLDOWN: i64 = 3;
+// minions summoned at once, and how far from the boss they appear
+pub const SUMMON_COUNT: usize = 2;
+pub const SUMMON_RADIUS: i16 = 2;
+// tiles cursed at once, and how far from the boss
+pub const CURSE_COUNT: usize = 3;
+pub const CURSE_RADIUS: i16 = 3;
+// teleports along roads land at least this far away
+pub const TELEPORT_MIN_DISTANCE: i16 = 3;
+// in endless runs a vanquished boss returns after this many monster turns,
+// at least this far from the player
+pub const BOSS_RESPAWN_TURNS: i64 = 20;
+pub const BOSS_RESPAWN_DISTANCE: i16 = 10;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BossAbility {
+  Summon,
+  Curse,
+  Teleport,
+}
+
+// the boss gets more dangerous as it runs out of lives
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BossPhase {
+  Dormant,
+  Summoning,
+  Cursing,
+  Teleporting,
+}
+
+// each phase, and the lowest remaining boss count that is still in it
+// a blow to the boss knocks its count down to the floor of its phase
+const PHASES: [(BossPhase, usize); 4] = [
+  (BossPhase::Dormant, 12),
+  (BossPhase::Summoning, 8),
+  (BossPhase::Cursing, 4),
+  (BossPhase::Teleporting, 1),
+];
+
+impl BossPhase {
+  pub fn of(remaining: usize) -> Self {
+    for (phase, floor) in PHASES {
+      if remaining > floor { return phase; }
+    }
+    BossPhase::Teleporting
+  }
+
+  pub fn floor(self) -> usize {
+    PHASES.iter().find(|(phase, _)| *phase == self).unwrap().1
+  }
+
+  pub fn abilities(self) -> &'static [BossAbility] {
+    match self {
+      BossPhase::Dormant => &[],
+      BossPhase::Summoning => &[BossAbility::Summon],
+      BossPhase::Cursing => &[BossAbility::Summon, BossAbility::Curse],
+      BossPhase::Teleporting => &[BossAbility::Curse, BossAbility::Teleport],
+    }
+  }
+
+  pub fn name(self) -> &'static str {
+    match self {
+      BossPhase::Dormant => "dormant",
+      BossPhase::Summoning => "summoning",
+      BossPhase::Cursing => "cursing",
+      BossPhase::Teleporting => "fleeing",
+    }
+  }
+
+  pub fn announcement(self) -> &'static str {
+    match self {
+      BossPhase::Dormant => "The GhostWitch slumbers",
+      BossPhase::Summoning => "The GhostWitch calls her minions!",
+      BossPhase::Cursing => "The GhostWitch curses the land!",
+      BossPhase::Teleporting => "The GhostWitch takes to the roads!",
+    }
+  }
+}
+
+// grass and towns become forest, roads and rivers are left alone
+pub fn curse(mut tile: Tile) -> Tile {
+  for t in tile.contents.iter_mut() {
+    if *t == Terrain::Grass || *t == Terrain::Town {
+      *t = Terrain::Forest;
+    }
+  }
+  tile
+}
+
+// whether the road on this tile continues into its neighbor
+pub fn road_connected(board: &Buffer2D<Tile>, p: Position, d: Dir4) -> bool {
+  let n = p + d.into();
+  board[p].contents[d.index()] == Terrain::Road
+    && board[n].contents[d.opposite().index()] == Terrain::Road
+}
+
+// every tile reachable by road from a position, with its distance
+pub fn road_network(board: &Buffer2D<Tile>, from: Position) -> Vec<(Position, i16)> {
+  let mut visited = WrapSet::new(BOARD_RECT);
+  let mut result = vec![];
+  let mut frontier = vec![from];
+  let mut next_frontier = vec![];
+  let mut distance = 0;
+  visited.insert(from);
+  while !frontier.is_empty() {
+    for p in frontier.drain(..) {
+      result.push((p, distance));
+      for d in Dir4::list() {
+        let n = p + d.into();
+        if road_connected(board, p, d) && !visited.contains(n) {
+          visited.insert(n);
+          next_frontier.push(n);
+        }
+      }
+    }
+    std::mem::swap(&mut frontier, &mut next_frontier);
+    distance += 1;
+  }
+  result
+}
@@ -0,0 +1,65 @@
+use crate::*;
+
+// who the player is for the run, picked before it starts
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PlayerClass {
+  Wanderer,
+  Ranger,
+  Builder,
+  Ferryman,
+}
+
+pub const CLASSES: [PlayerClass; 4] = [
+  PlayerClass::Wanderer,
+  PlayerClass::Ranger,
+  PlayerClass::Builder,
+  PlayerClass::Ferryman,
+];
+
+// starting stats and the rules a class bends
+pub struct ClassRules {
+  // added to the usual starting hp and tiles
+  pub hp: i64,
+  pub tiles: i64,
+  // forest edges work like roads for slipping out of a fight
+  pub forest_stride: bool,
+  // rivers never slow the player down
+  pub ford_rivers: bool,
+}
+
+impl PlayerClass {
+  pub fn rules(self) -> ClassRules {
+    match self {
+      PlayerClass::Wanderer => ClassRules {
+        hp: 0, tiles: 0, forest_stride: false, ford_rivers: false,
+      },
+      PlayerClass::Ranger => ClassRules {
+        hp: -1, tiles: 0, forest_stride: true, ford_rivers: false,
+      },
+      PlayerClass::Builder => ClassRules {
+        hp: -2, tiles: 15, forest_stride: false, ford_rivers: false,
+      },
+      PlayerClass::Ferryman => ClassRules {
+        hp: 0, tiles: -5, forest_stride: false, ford_rivers: true,
+      },
+    }
+  }
+
+  pub fn name(self) -> &'static str {
+    match self {
+      PlayerClass::Wanderer => "Wanderer",
+      PlayerClass::Ranger => "Ranger",
+      PlayerClass::Builder => "Builder",
+      PlayerClass::Ferryman => "Ferryman",
+    }
+  }
+
+  pub fn description(self) -> &'static str {
+    match self {
+      PlayerClass::Wanderer => "No tricks, no weaknesses",
+      PlayerClass::Ranger => "Slips out of fights through the forest",
+      PlayerClass::Builder => "More tiles, less health",
+      PlayerClass::Ferryman => "Rivers never slow them down, fewer tiles",
+    }
+  }
+}
@@ -0,0 +1,311 @@
+use crate::*;
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+
+// what the difficulty curve is measured against
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Pacing {
+  MonsterTurns,
+  TilesPlaced,
+  PlayerLevel,
+}
+
+// how far along the run is
+#[derive(Clone, Copy, Debug)]
+pub struct Progress {
+  pub monster_turns: i64,
+  pub tiles_placed: i64,
+  pub player_level: i64,
+}
+
+impl Progress {
+  pub fn measure(&self, pacing: Pacing) -> i64 {
+    match pacing {
+      Pacing::MonsterTurns => self.monster_turns,
+      Pacing::TilesPlaced => self.tiles_placed,
+      Pacing::PlayerLevel => self.player_level,
+    }
+  }
+}
+
+pub type SpawnTable = &'static [(EnemyType, u32)];
+
+pub struct DirectorConfig {
+  pub pacing: Pacing,
+  // (progress, percent) points of the budget multiplier
+  // interpolated linearly, flat past either end
+  pub curve: &'static [(i64, i64)],
+  // which spawn table is used once progress reaches each threshold
+  pub tables: &'static [(i64, SpawnTable)],
+  // each turn, every void space produces this many spawn points
+  pub points_per_frontier: i64,
+  // when a monster spawns, this many points are consumed
+  pub spawn_cost: i64,
+  // a frontier space rolls against the supply out of this many
+  pub spawn_roll: u64,
+  // monsters don't appear this close to the player
+  pub player_clearance: i16,
+  // stop spawning when this many monsters are lurking in the void
+  // per frontier space (in percent), so they don't wall the player in
+  pub max_void_percent: usize,
+  pub max_enemies: usize,
+  // once progress reaches tough_after, spawns have a chance
+  // (units are 1/10 percent) to be tough variants
+  pub tough_after: i64,
+  pub tough_chance: u64,
+}
+
+pub const STANDARD_DIRECTOR: DirectorConfig = DirectorConfig {
+  pacing: Pacing::MonsterTurns,
+  curve: &[(0, 100), (200, 125), (400, 150)],
+  tables: &[
+    (0, &[
+      (EnemyType::Clyde, 4),
+      (EnemyType::Blinky, 2),
+      (EnemyType::Pinky, 3),
+    ]),
+    (60, &[
+      (EnemyType::Clyde, 3),
+      (EnemyType::Blinky, 3),
+      (EnemyType::Pinky, 3),
+      (EnemyType::Inky, 2),
+    ]),
+    (120, &[
+      (EnemyType::Clyde, 3),
+      (EnemyType::Blinky, 3),
+      (EnemyType::Pinky, 3),
+      (EnemyType::Inky, 2),
+      (EnemyType::Sue, 1),
+    ]),
+    (200, &[
+      (EnemyType::Clyde, 2),
+      (EnemyType::Blinky, 4),
+      (EnemyType::Pinky, 2),
+      (EnemyType::Inky, 3),
+      (EnemyType::Sue, 2),
+    ]),
+  ],
+  points_per_frontier: 1,
+  spawn_cost: 30,
+  spawn_roll: 5000,
+  player_clearance: 2,
+  max_void_percent: 25,
+  max_enemies: 80,
+  tough_after: 150,
+  tough_chance: 200,
+};
+
+// for endless runs: the budget keeps climbing long after a standard run
+// would have ended, and the mix leans harder on the nastier monsters
+pub const ENDLESS_DIRECTOR: DirectorConfig = DirectorConfig {
+  pacing: Pacing::MonsterTurns,
+  curve: &[(0, 100), (200, 125), (400, 150), (800, 225), (1600, 400)],
+  tables: &[
+    (0, STANDARD_DIRECTOR.tables[0].1),
+    (60, STANDARD_DIRECTOR.tables[1].1),
+    (120, STANDARD_DIRECTOR.tables[2].1),
+    (200, STANDARD_DIRECTOR.tables[3].1),
+    (400, &[
+      (EnemyType::Clyde, 1),
+      (EnemyType::Blinky, 4),
+      (EnemyType::Pinky, 2),
+      (EnemyType::Inky, 4),
+      (EnemyType::Sue, 3),
+    ]),
+    (700, &[
+      (EnemyType::Blinky, 3),
+      (EnemyType::Pinky, 1),
+      (EnemyType::Inky, 4),
+      (EnemyType::Sue, 4),
+    ]),
+  ],
+  points_per_frontier: 1,
+  spawn_cost: 30,
+  spawn_roll: 5000,
+  player_clearance: 2,
+  max_void_percent: 25,
+  max_enemies: 120,
+  tough_after: 150,
+  tough_chance: 300,
+};
+
+// for puzzles: every monster is placed by the scenario, none spawn
+pub const PUZZLE_DIRECTOR: DirectorConfig = DirectorConfig {
+  points_per_frontier: 0,
+  max_enemies: 0,
+  ..STANDARD_DIRECTOR
+};
+
+// one line of the spawn log
+#[derive(Clone, Debug)]
+pub struct SpawnRecord {
+  pub turn: i64,
+  pub progress: i64,
+  pub multiplier: i64,
+  pub supply: i64,
+  pub frontier: usize,
+  pub enemies: usize,
+  pub spawned: Vec<EnemyType>,
+}
+
+impl SpawnRecord {
+  pub const CSV_HEADER: &'static str =
+    "turn,progress,multiplier,supply,frontier,enemies,spawned,types";
+
+  pub fn csv(&self) -> String {
+    let types: Vec<String> = self.spawned.iter()
+      .map(|t| format!("{:?}", t))
+      .collect();
+    format!("{},{},{},{},{},{},{},{}",
+      self.turn, self.progress, self.multiplier, self.supply,
+      self.frontier, self.enemies, self.spawned.len(), types.join(" "))
+  }
+}
+
+// set SPAWN_LOG=<path> to append a csv of every monster turn to it
+// opened once per process, so every run of a session ends up in it
+fn spawn_log() -> &'static Mutex<Option<std::fs::File>> {
+  static LOG: OnceLock<Mutex<Option<std::fs::File>>> = OnceLock::new();
+  LOG.get_or_init(|| {
+    let file = std::env::var("SPAWN_LOG").ok().and_then(|path| {
+      let mut f = std::fs::OpenOptions::new().create(true).append(true).open(path).ok()?;
+      if f.metadata().ok()?.len() == 0 {
+        writeln!(f, "{}", SpawnRecord::CSV_HEADER).ok()?;
+      }
+      Some(f)
+    });
+    Mutex::new(file)
+  })
+}
+
+fn write_spawn_log(line: &str) {
+  let mut log = spawn_log().lock().unwrap();
+  if let Some(f) = log.as_mut() {
+    if writeln!(f, "{}", line).is_err() {
+      warn!("failed to write spawn log");
+      *log = None;
+    }
+  }
+}
+
+// decides when and where monsters appear
+pub struct Director {
+  pub config: &'static DirectorConfig,
+  pub supply: i64,
+  pub turn: i64,
+}
+
+impl Director {
+  pub fn new(config: &'static DirectorConfig, seed: u64) -> Self {
+    // each run starts with a line of its own, so runs can be told apart
+    write_spawn_log(&format!("# run seed={}", seed));
+    Self { config, supply: 0, turn: 0 }
+  }
+
+  // percent multiplier on spawn points at this point in the run
+  pub fn multiplier(&self, progress: Progress) -> i64 {
+    let x = progress.measure(self.config.pacing);
+    let curve = self.config.curve;
+    let Some(&(x0, y0)) = curve.first() else { return 100; };
+    if x <= x0 { return y0; }
+    for w in curve.windows(2) {
+      let (xa, ya) = w[0];
+      let (xb, yb) = w[1];
+      if x < xb {
+        return ya + (yb - ya) * (x - xa) / (xb - xa).max(1);
+      }
+    }
+    curve.last().unwrap().1
+  }
+
+  pub fn table(&self, progress: Progress) -> SpawnTable {
+    let x = progress.measure(self.config.pacing);
+    let mut table = self.config.tables[0].1;
+    for &(threshold, t) in self.config.tables {
+      if x >= threshold { table = t; }
+    }
+    table
+  }
+
+  pub fn pick_enemy(&self, progress: Progress, rng: &mut Rng) -> Enemy {
+    let t = self.pick_type(progress, rng);
+    let late = progress.measure(self.config.pacing) >= self.config.tough_after;
+    if late && roll_chance(rng, self.config.tough_chance) {
+      Enemy::tough(t)
+    } else {
+      Enemy::new(t)
+    }
+  }
+
+  pub fn pick_type(&self, progress: Progress, rng: &mut Rng) -> EnemyType {
+    let table = self.table(progress);
+    let total: u32 = table.iter().map(|(_, w)| w).sum();
+    let mut roll = rng.next_u32() % total;
+    for &(t, w) in table {
+      if roll < w { return t; }
+      roll -= w;
+    }
+    unreachable!()
+  }
+
+  // charge the supply for a monster appearing
+  pub fn spend(&mut self) {
+    self.supply -= self.config.spawn_cost;
+  }
+
+  // runs once per monster turn and picks what spawns where
+  // the caller is expected to spend() for each spawn
+  pub fn plan_spawns(
+    &mut self,
+    progress: Progress,
+    frontier: &WrapSet,
+    enemies: &WrapMap<Enemy>,
+    board: &Buffer2D<Tile>,
+    player_pos: Position,
+    rng: &mut Rng,
+  ) -> Vec<(Enemy, Position)> {
+    self.turn += 1;
+    let multiplier = self.multiplier(progress);
+    let gain = frontier.len() as i64 * self.config.points_per_frontier;
+    self.supply += gain * multiplier / 100;
+
+    let mut in_void = 0;
+    for (&p, _) in enemies.iter() {
+      if board[p] == Tile::default() { in_void += 1; }
+    }
+    let void_cap = frontier.len() * self.config.max_void_percent / 100;
+
+    let mut spawns = vec![];
+    let mut budget = self.supply;
+    for &p in frontier.iter() {
+      if enemies.len() + spawns.len() >= self.config.max_enemies { break; }
+      if in_void + spawns.len() >= void_cap.max(1) { break; }
+      if enemies.contains_key(p) {
+        // don't spawn a monster if there's already a monster
+        continue;
+      }
+      if torus_max_norm(BOARD_RECT, p - player_pos) <= self.config.player_clearance {
+        continue;
+      }
+      if ((rng.next_u64() % self.config.spawn_roll) as i64) < budget {
+        spawns.push((self.pick_enemy(progress, rng), p));
+        budget -= self.config.spawn_cost;
+      }
+    }
+
+    self.record(SpawnRecord {
+      turn: self.turn,
+      progress: progress.measure(self.config.pacing),
+      multiplier,
+      supply: self.supply,
+      frontier: frontier.len(),
+      enemies: enemies.len(),
+      spawned: spawns.iter().map(|(nme, _)| nme.t).collect(),
+    });
+    spawns
+  }
+
+  fn record(&mut self, record: SpawnRecord) {
+    write_spawn_log(&record.csv());
+  }
+}
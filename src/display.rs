@@ -76,6 +76,46 @@ impl Display {
     Self{ camera_focus, resources, render_to, texture, dim, }
   }
 
+  // copies the display texture to the window, scaled to fit
+  pub fn present(&self) {
+    let scale = self.scale();
+    set_default_camera();
+    clear_background(BLACK);
+
+    draw_texture_ex(
+      &self.texture,
+      (screen_width() - (scale * self.dim.x)) * 0.5,
+      (screen_height() - (scale * self.dim.y)) * 0.5,
+      WHITE,
+      DrawTextureParams {
+        dest_size: Some(vec2(
+          scale * self.dim.x,
+          scale * self.dim.y,
+        )),
+        flip_y: true,
+        ..Default::default()
+      },
+    );
+  }
+
+  // how much the display texture is scaled to fit the window
+  pub fn scale(&self) -> f32 {
+    f32::min(
+      screen_width() / self.dim.x,
+      screen_height() / self.dim.y,
+    )
+  }
+
+  // converts a window position (e.g. the mouse) into display coordinates
+  pub fn screen_to_display(&self, p: Vec2) -> Vec2 {
+    let scale = self.scale();
+    let origin = Vec2 {
+      x: (screen_width() - (scale * self.dim.x)) * 0.5,
+      y: (screen_height() - (scale * self.dim.y)) * 0.5,
+    };
+    (p - origin) / scale
+  }
+
   pub fn camera_wrap_bounds(&self) -> Rect {
     let x: f32 = (self.camera_focus.x-BOARD_RECT.width/2) as f32 * DISPLAY_GRID.full_tile_size().x;
     let y: f32 = (self.camera_focus.y-BOARD_RECT.height/2) as f32 * DISPLAY_GRID.full_tile_size().y;
@@ -101,6 +101,26 @@ impl Shadows {
   }
 }
 
+// everything visible from a spot on the board, wrapping around the torus
+pub fn torus_fov(
+  from: Position,
+  distance: u8,
+  blocked: impl Fn(Position) -> bool,
+) -> WrapSet {
+  let mut seen = WrapSet::new(BOARD_RECT);
+  for rotation in 0..4 {
+    scan_quadrant(
+      BOARD_RECT.wrap(from),
+      rotation,
+      distance,
+      |p, d| BOARD_RECT.wrap(p + d.into()),
+      &blocked,
+      |p| seen.insert(p),
+    );
+  }
+  seen
+}
+
 pub fn scan_quadrant<Pos>(
   from: Pos,
   rotation: i8,
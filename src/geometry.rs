@@ -534,6 +534,126 @@ impl<T> std::ops::IndexMut<IVec> for Buffer2D<T> {
 
 //}}}
 
+// DijkstraMap{{{
+// distances over a wrapping grid, walking out from a set of sources
+// the cost callback prices each step between neighbors, None means the step
+// can't be taken. costs should not be negative
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DijkstraMap {
+  pub dist: Buffer2D<i16>,
+  pub neighbors: &'static [IVec],
+}
+
+impl DijkstraMap {
+  pub const UNREACHABLE: i16 = i16::MAX;
+  pub const ORTHOGONAL: [IVec; 4] = [
+    IVec{x:  1, y:  0},
+    IVec{x:  0, y:  1},
+    IVec{x: -1, y:  0},
+    IVec{x:  0, y: -1},
+  ];
+
+  pub fn new(rect: IRect) -> Self {
+    DijkstraMap {
+      dist: Buffer2D::new(Self::UNREACHABLE, rect),
+      neighbors: &Self::ORTHOGONAL,
+    }
+  }
+
+  // steps diagonally as well
+  pub fn with_diagonals(mut self) -> Self {
+    self.neighbors = &IVec::DIRS;
+    self
+  }
+
+  pub fn rect(&self) -> IRect {
+    self.dist.rect
+  }
+
+  pub fn is_reachable(&self, pos: Position) -> bool {
+    self.dist[pos] != Self::UNREACHABLE
+  }
+
+  // sources start at the given distance, which may be negative
+  pub fn fill(
+    &mut self,
+    sources: impl IntoIterator<Item = (Position, i16)>,
+    cost: impl Fn(Position, Position) -> Option<i16>,
+  ) {
+    use std::collections::BinaryHeap;
+    use std::cmp::Reverse;
+
+    let rect = self.dist.rect;
+    self.dist.fill(Self::UNREACHABLE);
+    let mut queue = BinaryHeap::new();
+    for (p, d) in sources {
+      let p = rect.wrap(p);
+      if d < self.dist[p] {
+        self.dist[p] = d;
+        queue.push(Reverse((d, p)));
+      }
+    }
+    while let Some(Reverse((d, p))) = queue.pop() {
+      if d > self.dist[p] { continue; }
+      for &v in self.neighbors {
+        let n = rect.wrap(p + v);
+        let Some(step) = cost(p, n) else { continue; };
+        let nd = d.saturating_add(step.max(0));
+        if nd < self.dist[n] {
+          self.dist[n] = nd;
+          queue.push(Reverse((nd, n)));
+        }
+      }
+    }
+  }
+
+  // a map for running away from the sources of this one
+  // following it downhill leads away from the sources, but a coefficient
+  // above 1 lets it prefer open escape routes over dead ends nearby
+  pub fn flee(
+    &self,
+    coefficient: f32,
+    cost: impl Fn(Position, Position) -> Option<i16>,
+  ) -> DijkstraMap {
+    let rect = self.dist.rect;
+    let sources: Vec<(Position, i16)> = rect.iter()
+      .filter(|&p| self.is_reachable(p))
+      .map(|p| (p, (-coefficient * self.dist[p] as f32) as i16))
+      .collect();
+    let mut result = DijkstraMap {
+      dist: Buffer2D::new(Self::UNREACHABLE, rect),
+      neighbors: self.neighbors,
+    };
+    result.fill(sources, cost);
+    result
+  }
+
+  // the neighbors with the lowest distance, if they improve on pos
+  pub fn downhill(&self, pos: Position) -> Vec<Position> {
+    let mut best = self.dist[pos];
+    let mut result = vec![];
+    for &v in self.neighbors {
+      let n = pos + v;
+      if self.dist[n] < best {
+        best = self.dist[n];
+        result.clear();
+      }
+      if self.dist[n] == best && best < self.dist[pos] {
+        result.push(n);
+      }
+    }
+    result
+  }
+}
+
+impl std::ops::Index<IVec> for DijkstraMap {
+  type Output = i16;
+  fn index(&self, pos: IVec) -> &i16 {
+    &self.dist[pos]
+  }
+}
+//}}}
+
 pub fn cardinal_alignment(from: Position, to: Position) -> Option<Dir4> {//{{{
   use core::cmp::Ordering;
   let delta = to - from;
@@ -10,6 +10,13 @@ pub enum Input {
   Discard,
   LevelUp,
   Mute,
+  CycleLog,
+  ToggleFog,
+  ToggleIntents,
+  ToggleAnimDebug,
+  SkipAnimations,
+  // pick a class, one of the offered perks, or a held prize
+  Choose(usize),
 }
 
 static mut INPUT_MAP: &mut [(KeyCode, Input)] = &mut [
@@ -41,6 +48,15 @@ static mut INPUT_MAP: &mut [(KeyCode, Input)] = &mut [
   (KeyCode::Backspace, Input::Discard),
 
   (KeyCode::M, Input::Mute),
+  (KeyCode::Tab, Input::CycleLog),
+  (KeyCode::F, Input::ToggleFog),
+  (KeyCode::I, Input::ToggleIntents),
+  (KeyCode::F3, Input::ToggleAnimDebug),
+  (KeyCode::Space, Input::SkipAnimations),
+  (KeyCode::Key1, Input::Choose(0)),
+  (KeyCode::Key2, Input::Choose(1)),
+  (KeyCode::Key3, Input::Choose(2)),
+  (KeyCode::Key4, Input::Choose(3)),
 ];
 
 pub fn get_input() -> Option<Input> {
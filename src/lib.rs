@@ -1,7 +1,7 @@
 
 pub type Seconds = f64;
 pub type UnitId = u64;
-pub type DMap = Buffer2D<i16>;
+pub type DMap = DijkstraMap;
 
 pub const BOARD_RECT: IRect = IRect { x: 0, y:0, width: 50, height: 50 };
 pub const PLAYER_UNIT_ID: UnitId = 0;
@@ -15,6 +15,8 @@ pub use display::*;
 
 pub mod animation;
 pub use crate::animation::*;
+pub mod tween;
+pub use crate::tween::*;
 
 pub mod random;
 pub use crate::random::*;
@@ -35,6 +37,29 @@ pub use util::*;
 
 pub mod fov;
 
+pub mod behavior;
+pub use crate::behavior::*;
+
+pub mod director;
+pub use crate::director::*;
+pub mod boss;
+pub use crate::boss::*;
+pub mod perks;
+pub use crate::perks::*;
+pub mod classes;
+pub use crate::classes::*;
+pub mod modes;
+pub use crate::modes::*;
+pub mod storage;
+pub mod profile;
+pub use crate::profile::*;
+pub mod quests;
+pub use crate::quests::*;
+pub mod puzzles;
+pub use crate::puzzles::*;
+pub mod scores;
+pub use crate::scores::*;
+
 
 pub use macroquad::prelude::*;
 
@@ -124,6 +149,21 @@ impl Tile {
     }
     n
   }
+
+  // anywhere on the tile, center included
+  pub fn has(self, t: Terrain) -> bool {
+    self.contents.contains(&t)
+  }
+
+  // void and forest can't be seen through
+  pub fn blocks_sight(self) -> bool {
+    self == Tile::default() || self.has(Terrain::Forest)
+  }
+
+  // thick forest and towns stop projectiles
+  pub fn blocks_shots(self) -> bool {
+    self.count(Terrain::Forest) + self.count(Terrain::Town) >= 3
+  }
 }
 
 
@@ -154,23 +194,106 @@ pub enum EnemyType {
   Clyde, //moves randomly
   Blinky, //chases player
   Pinky, //avoids other enemies
+  Inky, //flanks the player
+  Sue, //shoots the player from a distance
   GhostWitch, //the boss
 }
 
 impl EnemyType {
-  pub const fn list() -> [EnemyType; 4] {
-    const LIST: [EnemyType;4] = [EnemyType::Clyde,
+  pub const fn list() -> [EnemyType; 6] {
+    const LIST: [EnemyType;6] = [EnemyType::Clyde,
                                  EnemyType::Blinky,
                                  EnemyType::Pinky,
+                                 EnemyType::Inky,
+                                 EnemyType::Sue,
                                  EnemyType::GhostWitch];
     LIST
   }
+
+  // (hp, attack) for a freshly spawned enemy of this type
+  pub const fn stats(self) -> (i64, i64) {
+    match self {
+      EnemyType::Clyde => (1, 1),
+      EnemyType::Blinky => (1, 1),
+      EnemyType::Pinky => (1, 1),
+      // sturdy, it takes two blows
+      EnemyType::Inky => (2, 1),
+      // frail, but its shots and blows hurt
+      EnemyType::Sue => (1, 2),
+      // her count is knocked down one blow at a time,
+      // and the last GhostWitch takes two blows
+      EnemyType::GhostWitch => (2, 1),
+    }
+  }
 }
 
 #[repr(u8)]
 #[derive(Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
 pub enum Prize {
   Heal,
+  // a handful of extra tiles
+  Tiles,
+  // swap the current tile for a new one
+  Reroll,
+  // monsters sit out their next turn
+  Freeze,
+  // the whole map shows through the fog of war
+  Reveal,
+  // slays the monsters next to the player
+  Bomb,
+}
+
+// how often each prize turns up
+pub const PRIZE_TABLE: &[(Prize, u32)] = &[
+  (Prize::Heal, 4),
+  (Prize::Tiles, 3),
+  (Prize::Reroll, 3),
+  (Prize::Freeze, 2),
+  (Prize::Reveal, 1),
+  (Prize::Bomb, 2),
+];
+
+impl Prize {
+  // reveal is no use without the fog of war, so it only turns up with it
+  pub fn roll(rng: &mut Rng, fog_of_war: bool) -> Self {
+    let table: Vec<(Prize, u32)> = PRIZE_TABLE.iter().copied()
+      .filter(|&(prize, _)| fog_of_war || prize != Prize::Reveal)
+      .collect();
+    let total: u32 = table.iter().map(|(_, w)| w).sum();
+    let mut roll = rng.next_u32() % total;
+    for (prize, w) in table {
+      if roll < w { return prize; }
+      roll -= w;
+    }
+    unreachable!()
+  }
+
+  // held prizes go in the inventory, the rest are used on pickup
+  pub fn held(self) -> bool {
+    self != Prize::Heal
+  }
+
+  pub fn color(self) -> Color {
+    match self {
+      Prize::Heal => RED,
+      Prize::Tiles => SKYBLUE,
+      Prize::Reroll => YELLOW,
+      Prize::Freeze => BLUE,
+      Prize::Reveal => WHITE,
+      Prize::Bomb => ORANGE,
+    }
+  }
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Prize::Heal => "Heal",
+      Prize::Tiles => "Tile bundle",
+      Prize::Reroll => "Reroll",
+      Prize::Freeze => "Freeze",
+      Prize::Reveal => "Reveal",
+      Prize::Bomb => "Bomb",
+    }
+  }
 }
 
 static mut NEXT_UNIT_ID: UnitId = 10;
@@ -186,34 +309,121 @@ fn next_unit_id() -> UnitId {
 #[derive(Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
 pub struct Enemy {
   pub id: UnitId,
-  pub t: EnemyType
+  pub t: EnemyType,
+  pub hp: i64,
+  pub hp_max: i64,
+  // damage dealt to the player for each exchange of blows
+  pub attack: i64,
 }
 
 impl Enemy {
   pub fn new(nme_type: EnemyType) -> Self {
     let id = next_unit_id();
     let t = nme_type;
-    Enemy { id, t}
+    let (hp, attack) = t.stats();
+    Enemy { id, t, hp, hp_max: hp, attack }
+  }
+
+  // a sturdier version that shows up later in a run
+  pub fn tough(nme_type: EnemyType) -> Self {
+    let mut nme = Self::new(nme_type);
+    nme.hp += 1;
+    nme.hp_max += 1;
+    nme
+  }
+
+  // one exchange of blows, the enemy hits back either way
+  // returns whether it was slain
+  pub fn take_blow(&mut self) -> bool {
+    if self.hp <= 1 { return true; }
+    self.hp -= 1;
+    false
+  }
+
+  // health marks drawn over a wounded enemy
+  pub fn pips(&self) -> i64 {
+    if self.hp < self.hp_max { self.hp } else { 0 }
   }
 }
 
-#[derive(Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Hash, Debug)]
-pub struct Quest {
-  pub target: EnemyType,
-  pub quota: u64,
-  pub id: u64,
+// after the enemy at target falls, the enemies behind it move up
+// each one steps into the space the one in front of it left
+pub fn scooch_moves(
+  crowd: &Map<Position, u8>,
+  enemies: &WrapMap<Enemy>,
+  target: Position,
+) -> Vec<(Position, Position)> {
+  let mut moves = vec![];
+  let mut vacated = target;
+  let mut dist = 0;
+  'scooch: loop {
+    for d in Dir4::list() {
+      let neighbor = vacated + d.into();
+      if let Some(&dist2) = crowd.get(&neighbor) {
+        // enemies only want to scooch closer
+        if dist2 <= dist { continue; }
+        if enemies.contains_key(neighbor) {
+          moves.push((neighbor, vacated));
+          vacated = neighbor;
+          dist = dist2;
+          continue 'scooch;
+        }
+      }
+    }
+    break;
+  }
+  moves
 }
 
-impl Quest {
-  pub fn new() -> Self {
-    let target = EnemyType::Blinky;
-    let quota = 0;
-    let id = next_unit_id();
+// one exchange of blows in a crowd fight
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Blow {
+  // the enemy took the hit and is still standing, as it is afterwards
+  Hit(Enemy),
+  // the enemy fell, and the enemies behind it made these moves
+  Slain(Enemy, Vec<(Position, Position)>),
+}
 
-    Quest {target, quota, id}
+impl Blow {
+  pub fn slain(&self) -> Option<EnemyType> {
+    match self {
+      Blow::Slain(nme, _) => Some(nme.t),
+      Blow::Hit(_) => None,
+    }
   }
 }
 
+// the crowd fight at target, without any animation
+// each exchange costs the player the attack of whoever stands there,
+// until the spot is empty or dead(hp) says the player has fallen
+// returns every exchange in order, for the game to animate
+pub fn fight_crowd(
+  enemies: &mut WrapMap<Enemy>,
+  crowd: &Map<Position, u8>,
+  target: Position,
+  hp: &mut i64,
+  dead: impl Fn(i64) -> bool,
+) -> Vec<Blow> {
+  let mut blows = vec![];
+  while let Some(nme) = enemies.get_mut(target) {
+    if dead(*hp) { break; }
+    *hp -= nme.attack;
+    if !nme.take_blow() {
+      blows.push(Blow::Hit(*nme));
+      continue;
+    }
+    let nme = enemies.remove(target).unwrap();
+    let moves = scooch_moves(crowd, enemies, target);
+    for &(from, to) in moves.iter() {
+      if let Some(nme) = enemies.remove(from) {
+        enemies.insert(to, nme);
+      }
+    }
+    blows.push(Blow::Slain(nme, moves));
+  }
+  blows
+}
+
 
 #[derive(Clone, Debug)]
 pub struct WrapMap<V> {
@@ -365,23 +575,6 @@ pub fn subtile_neighbors(st: (Position, Dir4)) -> [(Position, Dir4);4] {
   neighbors
 }
 
-pub fn draw_quest(display: &Display, r: &Rect, quest: &Quest) {
-  display.draw_img(*r, BEIGE, &SCROLL);
-  let mut er: Rect = r.clone();
-  er.scale(0.5, 0.5);
-  er = er.offset(Vec2{ x: r.w/4., y: r.h /4. });
-  display.draw_img(er, BLACK, &enemy_img(quest.target, false));
-  let quest_text = format!("{}", quest.quota);
-  let font_size = 50;
-  let font_scale = 1.;
-  let textdim: TextDimensions = measure_text(&quest_text, None, font_size, font_scale);
-  let margin = 0.;
-  let text_x = er.x + textdim.width * 0.8 - margin;
-  let text_y = er.y + textdim.height * 0.2;
-  draw_text(&quest_text, text_x, text_y, font_size.into(), BLACK);
-}
-
-
 pub fn torus_max_norm(bounds: IRect, v: IVec) -> i16 {
   let w = bounds.wrap(v);
   let y = (w.y - bounds.y).min(bounds.y + bounds.height - w.y);
@@ -389,6 +582,16 @@ pub fn torus_max_norm(bounds: IRect, v: IVec) -> i16 {
   x.max(y)
 }
 
+// the shortest displacement on the torus equivalent to v
+pub fn torus_offset(bounds: IRect, v: IVec) -> IVec {
+  let centered = IRect {
+    x: -bounds.width / 2,
+    y: -bounds.height / 2,
+    ..bounds
+  };
+  centered.wrap(v)
+}
+
 
 use macroquad::audio::Sound;
 use std::rc::Rc;
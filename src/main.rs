@@ -9,16 +9,15 @@ use macroquad::audio::{load_sound_from_bytes, Sound};
 type Path = &'static str;
 type RegionId = u16;
 
-// each turn, every void space produces a spawn point
-// they increase monster spawn chance
-// when a monster spawns, these are consumed
-const MONSTER_SPAWN_POINTS: i64 = 30;
 const QUEST_SPAWN_CHANCE: u64 = 83; // units are 1/10 percent, roughly once in 12 tiles
-const QUEST_MIN: u64 = 3;
-const FOREST_ESCAPE_CHANCE: u64 = 250;
 const REGION_REWARD_THRESHOLD: i64 = 4;
 const NUM_BOSSES: usize = 15;
-const QUEST_REWARD: i64 = 5;
+// how many prizes the player can hold
+const INVENTORY_SIZE: usize = 3;
+const TILE_BUNDLE: i64 = 5;
+// closing a region at least this big has a chance (1/10 percent) to drop a prize
+const PRIZE_DROP_SIZE: i64 = 10;
+const PRIZE_DROP_CHANCE: u64 = 300;
 
 const STARTING_HP: i64 = 7;
 const STARTING_TILES: i64 = 35;
@@ -27,6 +26,8 @@ const DEBUG_IMMORTAL: bool = false;
 const BOSS_LOCATION:IVec = IVec::ZERO;
 
 const BASE_ANIMATION_DURATION: f64 = 0.5;
+// how far the player can see in fog of war mode
+const PLAYER_SIGHT_RADIUS: u8 = 6;
 
 
 //const MONSTER_COLOR: Color = PURPLE;
@@ -40,9 +41,28 @@ struct UIState {
   hud: Ref<Hud>,
   camera_ref: Ref<IVec>,
   compass_flash: f32,
+  // which event log entry the camera is panned to, if any
+  log_cursor: Option<usize>,
   // record where stuff gets drawn in ui
   layout: Map<HudItem, Rect>,
 
+  // Fog of war
+  fog_of_war: bool,
+  // what the player can see right now
+  fog_visible: WrapSet,
+  // the board as the player last saw it
+  remembered: Buffer2D<Tile>,
+
+  // Enemy intents
+  show_intents: bool,
+  intents_dirty: bool,
+  intents: Vec<Intent>,
+  // enemies that will be next to the player after the next monster turn
+  threats: usize,
+
+  // lists the animation queue over the game
+  show_anim_debug: bool,
+
 
   // Audio
   sounds: Map<Path, Rc<Sound>>,
@@ -59,9 +79,21 @@ impl UIState {
       hud: Ref::new(Hud::new()),
       camera_ref: Ref::new(IVec::ZERO),
       compass_flash: 0.,
+      log_cursor: None,
 
       layout: Map::new(),
 
+      fog_of_war: false,
+      fog_visible: WrapSet::new(BOARD_RECT),
+      remembered: Buffer2D::new(Tile::default(), BOARD_RECT),
+
+      show_intents: false,
+      intents_dirty: true,
+      intents: Vec::new(),
+      threats: 0,
+
+      show_anim_debug: false,
+
       sounds: sounds.clone(),
 
     }
@@ -75,6 +107,9 @@ struct SealedState {
   player_next_tile: Tile,
   next_quest: Option<Quest>,
   score_tiles_placed:  i64,
+  score_largest_region: i64,
+  score_regions_closed: i64,
+  score_bosses_slain: i64,
   board: Buffer2D<Tile>,
   regions: Buffer2D<[RegionId;4]>,
   region_sizes: Map<RegionId, i64>,
@@ -85,32 +120,61 @@ struct SealedState {
   open_regions: Set<RegionId>,
   // positions bordering void
   void_frontier: WrapSet,
-  enemy_supply: i64,
+  director: Director,
+  // prizes the player has already been told there's no room for
+  no_room_logged: WrapSet,
 
   // undoable but why
   player_dmap: DMap,
-  nearest_enemy_dmap: DMap,
+  // spaces in line of sight of the player
+  player_fov: WrapSet,
+  // where each enemy last saw the player
+  last_seen: Map<UnitId, Position>,
+  // shots enemies have announced
+  aims: Map<UnitId, Aim>,
+  // monster turns until the boss uses another ability
+  boss_cooldown: i64,
+  // monster turns skipped because of a freeze
+  frozen_turns: i64,
+  // endless runs: monster turns until a vanquished boss returns
+  boss_respawn: Option<i64>,
+  // puzzles: the tiles still to come, next one last
+  tile_sequence: Option<Vec<Tile>>,
+  puzzle_solved: bool,
   player_tile_transform: D8,
+  behaviors: Behaviors,
 }
 
 impl SealedState {
-  pub fn new() -> Self {
+  pub fn new(mode: GameMode, seed: u64) -> Self {
     Self {
       player_next_tile: Tile::default(),
       player_immortal: std::env::var("IMMORTAL").is_ok() || DEBUG_IMMORTAL,
       next_quest: None,
       player_tile_transform: D8::E,
       board: Buffer2D::new(Tile::default(), BOARD_RECT),
-      enemy_supply: 0,
+      director: Director::new(mode.director(), seed),
       regions: Buffer2D::new([RegionId::MAX;4], BOARD_RECT),
       next_region_id: 1,
       open_regions: Set::new(),
       void_frontier: WrapSet::new(BOARD_RECT),
+      no_room_logged: WrapSet::new(BOARD_RECT),
       region_sizes: Map::new(),
       region_start: Map::new(),
-      player_dmap: Buffer2D::new(0, BOARD_RECT),
-      nearest_enemy_dmap: Buffer2D::new(0, BOARD_RECT),
+      player_dmap: DijkstraMap::new(BOARD_RECT),
+      behaviors: Behaviors::standard(),
+      player_fov: WrapSet::new(BOARD_RECT),
+      last_seen: Map::new(),
+      aims: Map::new(),
+      boss_cooldown: BOSS_COOLDOWN,
+      frozen_turns: 0,
+      boss_respawn: None,
+      tile_sequence: None,
+      puzzle_solved: false,
       score_tiles_placed: 0,
+      score_bosses_slain: 0,
+      score_largest_region: 0,
+      score_regions_closed: 0,
     }
   }
 }
@@ -170,19 +234,35 @@ impl Snapshots {
 
 struct SimulationState {
   player_pos: Position,
+  // direction of the player's last step
+  player_facing: Dir4,
   player_hp: i64,
   player_hp_max: i64,
   player_xp: i64,
   player_level: i64,
   player_tiles: i64,
   player_defeat: bool,
+  player_victory: bool,
   monster_turns: i64,
   score_min_hp: i64,
   enemies: WrapMap<Enemy>,
   num_bosses: usize,
+  // what rng started from, for the high score table
+  seed: u64,
   rng: Rng,
   quests: WrapMap<Quest>,
   prizes: WrapMap<Prize>,
+  class: PlayerClass,
+  mode: GameMode,
+  // the scenario being played in puzzle mode
+  puzzle: Option<Puzzle>,
+  perks: Vec<Perk>,
+  // prizes held for later
+  inventory: Vec<Prize>,
+  // perks offered by a pending level up, kept until one is taken
+  perk_choice: Vec<Perk>,
+  // whether the perk choice is on screen
+  showing_perks: bool,
 
   ui: UIState,
   sealed: SealedState,
@@ -203,6 +283,13 @@ pub struct Hud {
   pub highlighted_spaces: WrapSet,
   pub hidden_spaces: WrapSet,
   pub desire_path: Vec<Position>,
+  pub log: Vec<LogEntry>,
+  // where announced shots come from and which way they go
+  pub aims: Map<UnitId, (Position, Dir4)>,
+  pub boss_pos: Position,
+  pub boss_phase: BossPhase,
+  // seconds left to show the boss phase announcement
+  pub phase_banner: f32,
 }
 impl Hud {
   pub fn new() -> Self {
@@ -221,6 +308,11 @@ impl Hud {
       highlighted_spaces: WrapSet::new(BOARD_RECT),
       hidden_spaces: WrapSet::new(BOARD_RECT),
       desire_path: Vec::new(),
+      log: Vec::new(),
+      aims: Map::new(),
+      boss_pos: BOSS_LOCATION,
+      boss_phase: BossPhase::Dormant,
+      phase_banner: 0.,
     }
   }
 }
@@ -230,7 +322,57 @@ impl Hud {
 #[repr(u8)]
 #[derive(Eq, PartialEq, Clone, Copy)]
 pub enum HudItem{
-  Hp, Xp, Tile, SpeedPenalty, Bar, Arrows, DiscardHint, LevelHint
+  Hp, Xp, Tile, SpeedPenalty, Bar, Arrows, DiscardHint, LevelHint, Log, Threats,
+  Inventory
+}
+
+// a guess at an enemy's next move
+pub struct Intent {
+  pub from: Position,
+  pub to: Position,
+  // more than one of these means the move is a coin flip
+  pub options: Vec<Position>,
+}
+
+#[derive(Clone)]
+pub struct LogEntry {
+  pub text: String,
+  pub at: Position,
+  pub color: Color,
+}
+
+const LOG_LINES: usize = 5;
+const LOG_LINE_HEIGHT: f32 = 44.;
+
+// what stepping into a fight would cost
+#[derive(Clone, Copy, Debug)]
+pub struct CombatPreview {
+  pub damage: i64,
+  pub slain: usize,
+  pub quests_completed: usize,
+  pub lethal: bool,
+}
+
+impl CombatPreview {
+  pub fn text(&self) -> String {
+    let mut text = format!("-{} HP", self.damage);
+    if self.lethal { text.push('!'); }
+    if self.quests_completed > 0 { text.push_str(" +quest"); }
+    text
+  }
+
+  pub fn color(&self) -> Color {
+    if self.lethal { RED } else { ORANGE }
+  }
+}
+
+// which log entries are visible, keeping the selected one on screen
+fn log_window(len: usize, cursor: Option<usize>) -> std::ops::Range<usize> {
+  let end = match cursor {
+    Some(i) => (i + 1).max(LOG_LINES.min(len)),
+    None => len,
+  };
+  end.saturating_sub(LOG_LINES)..end
 }
 
 pub struct Ragdoll {
@@ -238,6 +380,8 @@ pub struct Ragdoll {
   pub img: Img,
   pub color: Color,
   pub dead: bool,
+  // health marks drawn over sturdy enemies
+  pub pips: i64,
 }
 pub type Particle = Ragdoll;
 
@@ -248,66 +392,186 @@ pub struct AnimTile {
 }
 
 impl SimulationState {
-  pub fn new(sounds: &Map<Path, Rc<Sound>>) -> Self {
-    let mut sim = SimulationState {
+  pub fn new(sounds: &Map<Path, Rc<Sound>>, class: PlayerClass, mode: GameMode) -> Self {
+    let mut sim = SimulationState::blank(sounds, class, mode, seed_from_current_time());
+
+    // initialize starting tiles
+    let boss_lair_tiles = boss_lair(&mut sim.rng);
+    sim.place_tile(Position { x: -1, y: 1 }, boss_lair_tiles[0]);
+    sim.place_tile(Position { x: 0, y: 1 }, boss_lair_tiles[1]);
+    sim.place_tile(Position { x: 1, y: 1 }, boss_lair_tiles[2]);
+    sim.place_tile(Position { x: -1, y: 0 }, boss_lair_tiles[3]);
+    sim.place_tile(Position { x: 0, y: 0 }, boss_lair_tiles[4]);
+    sim.place_tile(Position { x: 1, y: 0 }, boss_lair_tiles[5]);
+    sim.place_tile(Position { x: -1, y: -1 }, boss_lair_tiles[6]);
+    sim.place_tile(Position { x: 0, y: -1 }, boss_lair_tiles[7]);
+    sim.place_tile(Position { x: 1, y: -1 }, boss_lair_tiles[8]);
+    sim.spawn_enemy(EnemyType::GhostWitch, BOSS_LOCATION);
+    sim.start();
+    sim
+  }
+
+  // a puzzle's fixed board, monsters and tile sequence
+  pub fn new_puzzle(sounds: &Map<Path, Rc<Sound>>, puzzle: &Puzzle) -> Self {
+    let mut sim = SimulationState::blank(sounds, PlayerClass::Wanderer, GameMode::Puzzle, puzzle.seed);
+    sim.puzzle = Some(puzzle.clone());
+    sim.player_pos = puzzle.player;
+    if let Some(hp) = puzzle.hp {
+      sim.player_hp = hp;
+      sim.player_hp_max = hp;
+      sim.score_min_hp = hp;
+    }
+    for &(p, tile) in &puzzle.board {
+      sim.place_tile(p, tile);
+    }
+    sim.update_region_sizes();
+    for &(p, t) in &puzzle.enemies {
+      sim.spawn_enemy(t, p);
+    }
+    sim.num_bosses = 0;
+    if let Some((p, count)) = puzzle.boss {
+      sim.num_bosses = count;
+      sim.spawn_enemy(EnemyType::GhostWitch, p);
+    }
+    // one more than the sequence, since drawing the first tile costs one
+    sim.player_tiles = puzzle.sequence.len() as i64 + 1;
+    sim.sealed.tile_sequence = Some(puzzle.sequence.iter().rev().copied().collect());
+    unsafe {
+      let hud = sim.ui.hud.get();
+      hud.hp = sim.player_hp;
+      hud.tiles = sim.player_tiles;
+      hud.bosses = sim.num_bosses;
+      hud.boss_pos = puzzle.boss.map_or(BOSS_LOCATION, |(p, _)| p);
+      hud.boss_phase = BossPhase::of(sim.num_bosses);
+    }
+    sim.start();
+    sim
+  }
+
+  fn blank(sounds: &Map<Path, Rc<Sound>>, class: PlayerClass, mode: GameMode, seed: u64) -> Self {
+    let rules = class.rules();
+    let sim = SimulationState {
       player_pos: IVec::ONE,
-      player_hp: STARTING_HP,
-      player_hp_max: STARTING_HP,
+      player_facing: Dir4::Up,
+      player_hp: STARTING_HP + rules.hp,
+      player_hp_max: STARTING_HP + rules.hp,
       player_xp: 0,
       player_level: 1,
-      player_tiles: STARTING_TILES,
+      player_tiles: STARTING_TILES + rules.tiles,
       player_defeat: false,
+      player_victory: false,
       monster_turns: 0,
       enemies: WrapMap::new(BOARD_RECT),
       quests: WrapMap::new(BOARD_RECT),
       prizes: WrapMap::new(BOARD_RECT),
-      rng: from_current_time(),
+      seed,
+      rng: Rng::new(seed, 0),
       num_bosses: NUM_BOSSES,
+      perks: Vec::new(),
+      inventory: Vec::new(),
+      perk_choice: Vec::new(),
+      showing_perks: false,
 
-      sealed: SealedState::new(),
+      sealed: SealedState::new(mode, seed),
       ui: UIState::new(sounds),
       // score
-      score_min_hp: STARTING_HP,
+      score_min_hp: STARTING_HP + rules.hp,
+      class,
+      mode,
+      puzzle: None,
     };
+    unsafe {
+      let hud = sim.ui.hud.get();
+      hud.hp = sim.player_hp;
+      hud.tiles = sim.player_tiles;
+    }
+    sim
+  }
 
+  // once the board is set up
+  fn start(&mut self) {
+    self.ragdoll_ref(PLAYER_UNIT_ID);
+    self.update_player_fov();
+    self.next_tile();
 
-    // initialize starting tiles
-    let boss_lair_tiles = boss_lair(&mut sim.rng);
-    sim.place_tile(Position { x: -1, y: 1 }, boss_lair_tiles[0]);
-    sim.place_tile(Position { x: 0, y: 1 }, boss_lair_tiles[1]);
-    sim.place_tile(Position { x: 1, y: 1 }, boss_lair_tiles[2]);
-    sim.place_tile(Position { x: -1, y: 0 }, boss_lair_tiles[3]);
-    sim.place_tile(Position { x: 0, y: 0 }, boss_lair_tiles[4]);
-    sim.place_tile(Position { x: 1, y: 0 }, boss_lair_tiles[5]);
-    sim.place_tile(Position { x: -1, y: -1 }, boss_lair_tiles[6]);
-    sim.place_tile(Position { x: 0, y: -1 }, boss_lair_tiles[7]);
-    sim.place_tile(Position { x: 1, y: -1 }, boss_lair_tiles[8]);
-    sim.spawn_enemy(EnemyType::GhostWitch, BOSS_LOCATION);
-    sim.ragdoll_ref(PLAYER_UNIT_ID);
-    sim.next_tile();
+    self.ui.hud.desire_path.push(self.player_pos);
+  }
 
-    sim.ui.hud.desire_path.push(sim.player_pos);
+  // puzzles hand out their tiles in a fixed order, so nothing adds to them
+  pub fn fixed_tiles(&self) -> bool {
+    self.sealed.tile_sequence.is_some()
+  }
 
+  pub fn has_perk(&self, perk: Perk) -> bool {
+    self.perks.contains(&perk)
+  }
 
-    sim
+  pub fn take_perk(&mut self, perk: Perk) {
+    self.add_xp(-self.player_xp_next());
+    if perk == Perk::Vigor {
+      self.player_hp_max += 1;
+      let to = self.ui.layout[&HudItem::Hp].center();
+      self.launch_particle(self.player_pos, to,
+        HEART, RED,
+        3., 0.02
+      ).chain();
+    }
+    self.defer_play_sound(LEVEL_UP_SOUND);
+    self.full_heal().chain();
+    self.player_level += 1;
+    self.perks.push(perk);
+    let text = format!("Level {}: {} ({})",
+      self.player_level, perk.name(), perk.description());
+    self.log_event(text, self.player_pos, RED).chain();
+  }
+
+  pub fn run_summary(&self) -> RunSummary {
+    RunSummary {
+      won: self.player_victory,
+      bosses_slain: self.sealed.score_bosses_slain,
+      largest_region: self.sealed.score_largest_region,
+    }
+  }
+
+  pub fn score_record(&self) -> ScoreRecord {
+    let bosses_slain = self.sealed.score_bosses_slain;
+    let tiles_placed = self.sealed.score_tiles_placed;
+    let turns = self.sealed.director.turn;
+    let score = match self.mode {
+      GameMode::Standard | GameMode::Puzzle => ScoreRecord::compute_score(self.player_victory, tiles_placed,
+        self.score_min_hp, self.sealed.score_regions_closed, turns, bosses_slain),
+      GameMode::Endless => ScoreRecord::survival_score(turns),
+    };
+    ScoreRecord {
+      score,
+      won: self.player_victory,
+      seed: self.seed,
+      date: date_string(macroquad::miniquad::date::now()),
+      class: self.class.name().to_string(),
+      mode: self.mode.name().to_string(),
+      turns,
+      tiles_placed,
+      min_hp: self.score_min_hp,
+      regions_closed: self.sealed.score_regions_closed,
+      bosses_slain,
+    }
   }
 
   pub fn player_dead(&self) -> bool {
-    self.player_hp < 1 && !self.sealed.player_immortal
+    self.would_die(self.player_hp)
+  }
+
+  pub fn would_die(&self, hp: i64) -> bool {
+    hp < 1 && !self.sealed.player_immortal
   }
 
   pub fn transform_tile(&mut self, g: D8) {
-    let duration = 0.5;
-    let hudref = self.ui.hud.clone();
     let r = (g * Dir4::Right).radians();
     let t = g * self.sealed.player_tile_transform;
-    self.ui.animations.append(move |time| {
-      let p = time.progress(duration);
-      unsafe {
-        hudref.get().tile_rotation = r * p;
-      }
-      p < 1.
-    }).reserve(PLAYER_UNIT_ID);
+    self.ui.animations.append_tween(tween(
+      self.ui.hud.clone(), |hud| &mut hud.tile_rotation,
+      0., r, TILE_ROTATE_DURATION, TILE_ROTATE_EASE,
+    )).label("rotate tile").reserve(PLAYER_UNIT_ID);
     self.defer_set_hud(move |hud| {
       hud.tile_transform = t;
       hud.tile_rotation = 0.;
@@ -322,7 +586,13 @@ impl SimulationState {
     self.ui.animations.append(move |_| unsafe {
       (f)(hudref.get());
       false
-    })
+    }).label("set hud")
+  }
+
+  pub fn log_event(&mut self, text: String, at: Position, color: Color)
+    -> &mut Animation {
+    let entry = LogEntry { text, at, color };
+    self.defer_set_hud(move |hud| hud.log.push(entry.clone()))
   }
 
   pub fn defer_play_sound(&mut self, soundpath: Path) -> &mut Animation {
@@ -330,12 +600,14 @@ impl SimulationState {
     self.ui.animations.append(move |_| {
       play_sound(sound.clone());
       false
-    })
+    }).label("play sound")
   }
 
   pub fn spawn_enemy(&mut self, t: EnemyType, at: Position) {
-    let nme = Enemy::new(t);
-    self.sealed.enemy_supply -= MONSTER_SPAWN_POINTS;
+    self.place_enemy(Enemy::new(t), at);
+  }
+
+  pub fn place_enemy(&mut self, nme: Enemy, at: Position) {
     self.enemies.insert(at, nme);
     let rdr = self.ragdoll_ref(nme.id);
     if self.sealed.board[at] != Tile::default() {
@@ -376,25 +648,53 @@ impl SimulationState {
   }
 
 
+  // enemies that can see the player look alarmed
   pub fn set_enemy_alerts(&mut self, alerted: bool)  {
-    for d in Dir4::list() {
-      let neighbor = self.player_pos + d.into();
-      if self.sealed.board[neighbor] == Tile::default() {
-        continue;
-      }
-      let crowd = self.calculate_crowd(neighbor);
-      for &pos in crowd.keys() {
-        let nme = self.enemies[pos];
-        let rgr = self.ragdoll_ref(nme.id);
-        self.ui.animations.append(move |_| unsafe {
-          rgr.get().img = enemy_img(nme.t, alerted);
-          false
-        }).reserve(nme.id);
-      }
+    let mut watching = vec![];
+    for (&pos, &nme) in self.enemies.iter() {
+      if !self.sealed.player_fov.contains(pos) { continue; }
+      // monsters in void stay spooky
+      if self.sealed.board[pos] == Tile::default() { continue; }
+      watching.push(nme);
     }
-}
+    for nme in watching {
+      let rgr = self.ragdoll_ref(nme.id);
+      self.ui.animations.append(move |_| unsafe {
+        rgr.get().img = enemy_img(nme.t, alerted);
+        false
+      }).label("alert").reserve(nme.id);
+    }
+  }
+
+  // what the player can see in fog of war mode
+  // forest blocks the view, unless the player is in the forest too
+  pub fn update_fog(&mut self) {
+    let board = &self.sealed.board;
+    let in_forest = board[self.player_pos].has(Terrain::Forest);
+    let blocked = |p: Position| {
+      let tile = board[p];
+      tile == Tile::default() || (tile.blocks_sight() && !in_forest)
+    };
+    self.ui.fog_visible = fov::torus_fov(self.player_pos, PLAYER_SIGHT_RADIUS, blocked);
+    for &p in self.ui.fog_visible.iter() {
+      self.ui.remembered[p] = self.sealed.board[p];
+    }
+  }
+
+  pub fn update_player_fov(&mut self) {
+    let board = &self.sealed.board;
+    self.sealed.player_fov = fov::torus_fov(self.player_pos, SIGHT_RADIUS, |p| board[p].blocks_sight());
+  }
 
 
+  pub fn progress(&self) -> Progress {
+    Progress {
+      monster_turns: self.sealed.director.turn,
+      tiles_placed: self.sealed.score_tiles_placed,
+      player_level: self.player_level,
+    }
+  }
+
   pub fn player_xp_next(&self) -> i64 {
     self.player_level * 3
   }
@@ -454,20 +754,7 @@ impl SimulationState {
   pub fn place_tile(&mut self, position: Position, tile: Tile) {
     self.sealed.board[position] = tile;
     { // region tracking
-      // merge regions
-      for d in Dir4::list() {
-        self.fill_region_ids(position, d);
-      }
-
-      // new regions
-      for d in Dir4::list() {
-        self.fill_region_ids(position,d);
-        if self.sealed.regions[position][d.index()] == RegionId::MAX {
-          self.sealed.regions[position][d.index()] = self.sealed.next_region_id;
-          self.sealed.region_start.insert(self.sealed.next_region_id, (position, d));
-          self.sealed.next_region_id += 1;
-        }
-      }
+      self.assign_region_ids(position);
 
       // update void frontier
       self.sealed.void_frontier.remove(position);
@@ -478,17 +765,7 @@ impl SimulationState {
         }
       }
 
-      // rebuild open regions
-      self.sealed.open_regions.clear();
-      for &void_cell in self.sealed.void_frontier.iter() {
-        for d in Dir4::list() {
-          let cell = void_cell + d.into();
-          let regionid = self.sealed.regions[cell][d.opposite().index()];
-          if regionid < RegionId::MAX {
-            self.sealed.open_regions.insert(regionid);
-          }
-        }
-      }
+      self.rebuild_open_regions();
 
       // place quest
       if let Some(_) = self.sealed.next_quest {
@@ -497,6 +774,66 @@ impl SimulationState {
     }
   }
 
+  fn assign_region_ids(&mut self, position: Position) {
+    // merge regions
+    for d in Dir4::list() {
+      self.fill_region_ids(position, d);
+    }
+
+    // new regions
+    for d in Dir4::list() {
+      self.fill_region_ids(position,d);
+      if self.sealed.regions[position][d.index()] == RegionId::MAX {
+        self.sealed.regions[position][d.index()] = self.sealed.next_region_id;
+        self.sealed.region_start.insert(self.sealed.next_region_id, (position, d));
+        self.sealed.next_region_id += 1;
+      }
+    }
+  }
+
+  fn rebuild_open_regions(&mut self) {
+    self.sealed.open_regions.clear();
+    for &void_cell in self.sealed.void_frontier.iter() {
+      for d in Dir4::list() {
+        let cell = void_cell + d.into();
+        let regionid = self.sealed.regions[cell][d.opposite().index()];
+        if regionid < RegionId::MAX {
+          self.sealed.open_regions.insert(regionid);
+        }
+      }
+    }
+  }
+
+  // changes the terrain of a tile that is already on the board
+  // regions the tile was part of keep their ids, even if they are split
+  pub fn replace_tile(&mut self, position: Position, tile: Tile) {
+    let old = self.sealed.board[position];
+    self.sealed.board[position] = tile;
+    for d in Dir4::list() {
+      let i = d.index();
+      if old.contents[i] == tile.contents[i] && old.contents[4] == tile.contents[4] {
+        continue;
+      }
+      let rid = self.sealed.regions[position][i];
+      self.sealed.regions[position][i] = RegionId::MAX;
+      if self.sealed.region_start.get(&rid) == Some(&(position, d)) {
+        // move the region start to somewhere else in the region
+        self.sealed.region_start.remove(&rid);
+        'search: for p in BOARD_RECT.iter() {
+          for d2 in Dir4::list() {
+            if self.sealed.regions[p][d2.index()] == rid {
+              self.sealed.region_start.insert(rid, (p, d2));
+              break 'search;
+            }
+          }
+        }
+      }
+    }
+    self.assign_region_ids(position);
+    self.rebuild_open_regions();
+    self.update_region_sizes();
+  }
+
   pub fn update_region_sizes(&mut self) {
     self.sealed.region_sizes.clear();
     let mut v = vec![];
@@ -522,6 +859,14 @@ impl SimulationState {
     let (position, dir) = self.sealed.region_start[&rid];
     let terrain = self.sealed.board[position].contents[dir.index()];
     let size = self.sealed.region_sizes[&rid];
+    self.sealed.score_largest_region = self.sealed.score_largest_region.max(size);
+    self.sealed.score_regions_closed += 1;
+    for (_, quest) in self.quests.iter_mut() {
+      quest.credit_region(terrain, size);
+    }
+    if let Some(PuzzleGoal::Close { terrain: t, size: n }) = self.puzzle.as_ref().map(|p| p.goal) {
+      if terrain == t && size >= n { self.sealed.puzzle_solved = true; }
+    }
     if terrain == Terrain::River {
       // Cancel the reward if the region is a river without
       // a source
@@ -556,7 +901,9 @@ impl SimulationState {
     } else {
       size.saturating_sub(REGION_REWARD_THRESHOLD)
     };
-    let tile_reward = if size > REGION_REWARD_THRESHOLD { 1 } else { 0 };
+    let base_tiles = if size > REGION_REWARD_THRESHOLD { 1 } else { 0 };
+    let mut tile_reward = region_tile_reward(base_tiles, &self.perks);
+    if self.fixed_tiles() { tile_reward = 0; }
     if xp_reward > 0 {
       let to = self.ui.layout[&HudItem::Xp].center();
 
@@ -581,25 +928,124 @@ impl SimulationState {
       self.ui.animations.append_empty(0.).require(PLAYER_UNIT_ID);
       self.defer_play_sound(tile_sound()).chain();
       self.launch_particle(self.player_pos, to, TILE, SKYBLUE, 3., 0.1).chain();
-      self.add_tiles(1).chain();
+      self.add_tiles(tile_reward).chain();
+    }
+    if xp_reward > 0 || tile_reward > 0 {
+      let mut rewards = vec![];
+      if xp_reward > 0 { rewards.push(format!("+{} XP", xp_reward)); }
+      if tile_reward > 0 { rewards.push(format!("+{} tile", tile_reward)); }
+      let text = format!("{:?} region ({}) closed: {}", terrain, size, rewards.join(", "));
+      self.log_event(text, position, terrain.color()).chain();
+    }
+    // big regions sometimes leave a prize behind
+    let free = !self.enemies.contains_key(position)
+      && !self.quests.contains_key(position)
+      && !self.prizes.contains_key(position);
+    let drops = size >= PRIZE_DROP_SIZE && free && !self.fixed_tiles();
+    if drops && roll_chance(&mut self.rng, PRIZE_DROP_CHANCE) {
+      let prize = Prize::roll(&mut self.rng, self.ui.fog_of_war);
+      self.prizes.insert(position, prize);
+      let text = format!("{:?} region left a prize: {}", terrain, prize.name());
+      self.log_event(text, position, prize.color()).chain();
+    }
+  }
+
+  // heals are used right away, anything else is held if there is room
+  pub fn collect_prize(&mut self, at: Position, prize: Prize) {
+    if prize.held() && self.inventory.len() >= INVENTORY_SIZE {
+      if !self.sealed.no_room_logged.contains(at) {
+        self.sealed.no_room_logged.insert(at);
+        let text = format!("No room for {}", prize.name());
+        self.log_event(text, at, GRAY).chain();
+      }
+      return;
+    }
+    self.prizes.remove(at);
+    self.sealed.no_room_logged.remove(at);
+    let to = if prize.held() {
+      self.ui.layout[&HudItem::Inventory].center()
+    } else {
+      self.ui.layout[&HudItem::Hp].center()
+    };
+    self.ui.animations.append_empty(0.).reserve(PLAYER_UNIT_ID);
+    self.defer_play_sound(LEVEL_UP_SOUND).chain();
+    self.launch_particle(at, to,
+      prize_img(prize), prize.color(),
+      3., 0.02
+    ).chain();
+    if prize.held() {
+      self.inventory.push(prize);
+    } else {
+      self.use_prize(prize);
     }
   }
 
+  // reveal does nothing with the fog of war off, so it is kept for later
+  pub fn can_use_prize(&self, prize: Prize) -> bool {
+    prize != Prize::Reveal || self.ui.fog_of_war
+  }
+
+  pub fn use_prize(&mut self, prize: Prize) {
+    match prize {
+      Prize::Heal => {
+        self.full_heal().chain();
+      }
+      Prize::Tiles => {
+        let to = self.ui.layout[&HudItem::Tile].center();
+        self.ui.animations.append_empty(0.).require(PLAYER_UNIT_ID);
+        self.defer_play_sound(tile_sound()).chain();
+        self.launch_particle(self.player_pos, to, TILE, SKYBLUE, 3., 0.1).chain();
+        self.add_tiles(TILE_BUNDLE).chain();
+      }
+      Prize::Reroll => {
+        // a free discard
+        self.sealed.next_quest = None;
+        self.next_tile();
+        self.add_tiles(1).chain();
+      }
+      Prize::Freeze => {
+        self.sealed.frozen_turns += 1;
+      }
+      Prize::Reveal => {
+        for p in BOARD_RECT.iter() {
+          self.ui.remembered[p] = self.sealed.board[p];
+        }
+      }
+      Prize::Bomb => {
+        for d in Dir4::list() {
+          let p = self.player_pos + d.into();
+          // monsters in void are out of reach
+          if self.sealed.board[p] == Tile::default() { continue; }
+          let Some(nme) = self.enemies.get(p) else { continue; };
+          // the boss shrugs it off
+          if nme.t == EnemyType::GhostWitch { continue; }
+          self.blast_enemy(p, d);
+        }
+      }
+    }
+    let text = format!("Used {}", prize.name());
+    self.log_event(text, self.player_pos, prize.color()).chain();
+  }
+
   pub fn player_current_tile(&self) -> Tile {
     self.sealed.player_tile_transform * self.sealed.player_next_tile
   }
 
   // returns whether the next tile has any placeable spots
   pub fn next_tile(&mut self) -> bool {
-    self.sealed.player_next_tile = tiles::generate(&mut self.rng);
+    self.sealed.player_next_tile = match &mut self.sealed.tile_sequence {
+      Some(sequence) => sequence.pop().unwrap_or_default(),
+      None => tiles::generate(&mut self.rng),
+    };
     self.defer_set_hud(|hud| hud.tile_rotation = 0.)
       .reserve(PLAYER_UNIT_ID);
     self.add_tiles(-1).chain();
 
     // does the next tile have a quest?
-    if roll_chance(&mut self.rng, QUEST_SPAWN_CHANCE) {
+    if !self.fixed_tiles() && roll_chance(&mut self.rng, QUEST_SPAWN_CHANCE) {
       debug!("quest");
-      if let Some(quest) = eligible_for_quest(&self.enemies, &self.quests, &mut self.rng) {
+      let tile = self.sealed.player_next_tile;
+      if let Some(quest) = roll_quest(&self.enemies, &self.quests, tile, &mut self.rng) {
         debug!("{:?}", quest);
         self.sealed.next_quest = Some(quest);
       }
@@ -613,70 +1059,385 @@ impl SimulationState {
     false
   }
 
-  pub fn update_player_dmap(&mut self) {
-    self.sealed.player_dmap.fill(i16::MAX);
-    let mut d = 0;
-    let mut frontier = Vec::new();
-    frontier.push(self.player_pos);
-    let mut next_frontier = Vec::new();
-
-    loop {
-      while let Some(visit) = frontier.pop() {
-        if self.sealed.player_dmap[visit] > d {
-          self.sealed.player_dmap[visit] = d;
-          for d in Dir4::list() {
-            let neighbor = visit + d.into();
-            if self.sealed.player_dmap[neighbor] == i16::MAX
-              && self.sealed.board[neighbor] != Tile::default()
-            {
-              next_frontier.push(neighbor);
-            }
-          }
-        }
+  // progress on quests that depend on the board and where the player is
+  pub fn check_quests(&mut self) {
+    for (&p, quest) in self.quests.iter_mut() {
+      quest.check(p, &self.sealed.board, self.player_pos);
+    }
+  }
+
+  // hand out rewards for ready scrolls on or next to the player
+  // runs after the monsters have moved, so a lethal turn pays nothing
+  pub fn claim_quests(&mut self) {
+    // this runs after the monster turn, an immortal player always survives it
+    let hp = if self.player_defeat || self.player_dead() { 0 } else { self.player_hp.max(1) };
+    for p in claimable_quests(&self.quests, self.player_pos, hp) {
+      let Some(q) = self.quests.remove(p) else { continue; };
+      let to = self.ui.layout[&HudItem::Tile].center();
+      for i in 0..(q.reward as u8) {
+        let delay = f64::from(i)* 0.7 * BASE_ANIMATION_DURATION ;
+        self.ui.animations.append_empty(0.).require(PLAYER_UNIT_ID);
+        self.ui.animations.append_empty(delay).chain();
+        self.defer_play_sound(tile_sound()).chain();
+        self.launch_particle(p, to, TILE, SKYBLUE, 3., 0.1).chain();
+        self.add_tiles(1).chain();
       }
-      if next_frontier.len() == 0 {
-        break;
+      let text = format!("{} complete: +{} tiles", q.describe(), q.reward);
+      self.log_event(text, p, BEIGE).chain();
+
+      // the scroll leaves a prize behind
+      let prize = Prize::roll(&mut self.rng, self.ui.fog_of_war);
+      self.prizes.insert(p, prize);
+      if equivalent(p, self.player_pos) {
+        self.collect_prize(p, prize);
       }
-      next_frontier.sort();
-      next_frontier.dedup();
-      std::mem::swap(&mut frontier, &mut next_frontier);
-      d += 1;
     }
   }
 
-  pub fn update_nearest_dmap(&mut self) {
-    self.sealed.nearest_enemy_dmap.fill(i16::MAX);
-    let mut d = 0;
-    let mut frontier = Vec::new();
-    for (pos, nme) in self.enemies.iter() {
-      if nme.t == EnemyType::Pinky {
-        // pinkies shouldn't hide from each other
-        continue;
+  // a monster turn has passed, timed out quests are dropped
+  pub fn tick_quests(&mut self) {
+    for (p, &quest) in self.quests.clone().iter() {
+      let mut quest = quest;
+      if quest.tick() {
+        self.quests.insert(*p, quest);
+      } else {
+        self.quests.remove(*p);
+        let text = format!("{} failed", quest.describe());
+        self.log_event(text, *p, GRAY).chain();
       }
-      frontier.push(*pos);
     }
-    let mut next_frontier = Vec::new();
+  }
 
-    loop {
-      while let Some(visit) = frontier.pop() {
-        if self.sealed.nearest_enemy_dmap[visit] > d {
-          self.sealed.nearest_enemy_dmap[visit] = d;
-          for d in Dir4::list() {
-            let neighbor = visit + d.into();
-            if self.sealed.nearest_enemy_dmap[neighbor] == i16::MAX
-              && self.sealed.board[neighbor] != Tile::default()
-            {
-              next_frontier.push(neighbor);
-            }
-          }
+  pub fn update_player_dmap(&mut self) {
+    fill_dmap(&mut self.sealed.player_dmap, &self.sealed.board, vec![self.player_pos]);
+  }
+
+  // enemies remember where they last saw the player
+  // until they get there and find nobody
+  fn remembered_sightings(&self) -> Map<UnitId, Position> {
+    let mut last_seen = Map::new();
+    for (&pos, nme) in self.enemies.iter() {
+      if self.sealed.player_fov.contains(pos) {
+        last_seen.insert(nme.id, self.player_pos);
+      } else if let Some(&spot) = self.sealed.last_seen.get(&nme.id) {
+        if !equivalent(spot, pos) {
+          last_seen.insert(nme.id, spot);
         }
       }
-      if next_frontier.len() == 0 { break; }
-      next_frontier.sort();
-      next_frontier.dedup();
-      std::mem::swap(&mut frontier, &mut next_frontier);
-      d += 1;
     }
+    last_seen
+  }
+
+  // lets each kind of enemy get ready for the coming monster turn
+  pub fn prepare_enemy_behaviors(&mut self) {
+    self.sealed.last_seen = self.remembered_sightings();
+
+    let view = EnemyView {
+      board: &self.sealed.board,
+      enemies: &self.enemies,
+      quests: &self.quests,
+      prizes: &self.prizes,
+      player_pos: self.player_pos,
+      player_facing: self.player_facing,
+      player_dmap: &self.sealed.player_dmap,
+      player_fov: &self.sealed.player_fov,
+      last_seen: &self.sealed.last_seen,
+      forest_escape_chance: forest_escape_chance(&self.perks),
+    };
+    self.sealed.behaviors.prepare(&view);
+  }
+
+  pub fn enemy_pathfind(&mut self, pos: Position) -> Option<Position> {
+    let view = EnemyView {
+      board: &self.sealed.board,
+      enemies: &self.enemies,
+      quests: &self.quests,
+      prizes: &self.prizes,
+      player_pos: self.player_pos,
+      player_facing: self.player_facing,
+      player_dmap: &self.sealed.player_dmap,
+      player_fov: &self.sealed.player_fov,
+      last_seen: &self.sealed.last_seen,
+      forest_escape_chance: forest_escape_chance(&self.perks),
+    };
+    self.sealed.behaviors.choose_move(&view, pos, &mut self.rng)
+  }
+
+  // enemies that shoot announce a shot when they have a clear line to the
+  // player, then take it on their next turn after the player has moved
+  // returns whether the enemy used its turn
+  pub fn ranged_attack(&mut self, pos: Position, speed: f64) -> bool {
+    let Some(&nme) = self.enemies.get(pos) else { return false; };
+    if let Some(&aim) = self.sealed.aims.get(&nme.id) {
+      if aim.ready {
+        self.sealed.aims.remove(&nme.id);
+        self.fire_shot(nme, pos, aim.dir, speed);
+      }
+      return true;
+    }
+    if !self.sealed.player_fov.contains(pos) { return false; }
+    let Some(dir) = line_of_fire(&self.sealed.board, pos, self.player_pos) else {
+      return false;
+    };
+    self.sealed.aims.insert(nme.id, Aim { dir, ready: false });
+    let id = nme.id;
+    self.defer_set_hud(move |hud| { hud.aims.insert(id, (pos, dir)); })
+      .reserve(id);
+    true
+  }
+
+  // the boss uses one of its phase's abilities every few monster turns
+  pub fn boss_turn(&mut self, pos: Position) {
+    let abilities = BossPhase::of(self.num_bosses).abilities();
+    if abilities.is_empty() { return; }
+    self.sealed.boss_cooldown -= 1;
+    if self.sealed.boss_cooldown > 0 { return; }
+    self.sealed.boss_cooldown = BOSS_COOLDOWN;
+
+    let ability = abilities[self.rng.next_u32() as usize % abilities.len()];
+    let used = match ability {
+      BossAbility::Summon => self.boss_summon(pos),
+      BossAbility::Curse => self.boss_curse(pos),
+      BossAbility::Teleport => self.boss_teleport(pos),
+    };
+    if !used && ability != BossAbility::Summon {
+      self.boss_summon(pos);
+    }
+  }
+
+  // free spaces near a position that an enemy could be put on
+  fn open_spaces_near(&self, pos: Position, radius: i16) -> Vec<Position> {
+    let area = IRect {
+      x: pos.x - radius,
+      y: pos.y - radius,
+      width: 2 * radius + 1,
+      height: 2 * radius + 1,
+    };
+    area.iter().filter(|&p| {
+      self.sealed.board[p] != Tile::default()
+        && !self.enemies.contains_key(p)
+        && !self.quests.contains_key(p)
+        && !self.prizes.contains_key(p)
+        && !equivalent(p, self.player_pos)
+    }).collect()
+  }
+
+  pub fn boss_summon(&mut self, pos: Position) -> bool {
+    let mut spots = self.open_spaces_near(pos, SUMMON_RADIUS);
+    if spots.is_empty() { return false; }
+    shuffle(&mut spots, &mut self.rng);
+    let progress = self.progress();
+    for &p in spots.iter().take(SUMMON_COUNT) {
+      let nme = self.sealed.director.pick_enemy(progress, &mut self.rng);
+      self.place_enemy(nme, p);
+    }
+    self.log_event("GhostWitch summons minions".to_string(), pos, MONSTER_COLOR);
+    true
+  }
+
+  pub fn boss_curse(&mut self, pos: Position) -> bool {
+    let area = IRect {
+      x: pos.x - CURSE_RADIUS,
+      y: pos.y - CURSE_RADIUS,
+      width: 2 * CURSE_RADIUS + 1,
+      height: 2 * CURSE_RADIUS + 1,
+    };
+    let mut spots: Vec<Position> = area.iter().filter(|&p| {
+      let tile = self.sealed.board[p];
+      tile != Tile::default() && curse(tile) != tile
+    }).collect();
+    if spots.is_empty() { return false; }
+    shuffle(&mut spots, &mut self.rng);
+    for &p in spots.iter().take(CURSE_COUNT) {
+      self.replace_tile(p, curse(self.sealed.board[p]));
+    }
+    self.log_event("GhostWitch curses the land".to_string(), pos, MONSTER_COLOR);
+    true
+  }
+
+  pub fn boss_teleport(&mut self, pos: Position) -> bool {
+    let Some(&nme) = self.enemies.get(pos) else { return false; };
+    let mut spots: Vec<Position> = road_network(&self.sealed.board, pos)
+      .into_iter()
+      .filter(|&(p, distance)| {
+        distance >= TELEPORT_MIN_DISTANCE
+          && !self.enemies.contains_key(p)
+          && !self.quests.contains_key(p)
+          && !self.prizes.contains_key(p)
+          && !equivalent(p, self.player_pos)
+      })
+      .map(|(p, _)| p)
+      .collect();
+    if spots.is_empty() { return false; }
+    shuffle(&mut spots, &mut self.rng);
+    let to = spots[0];
+    self.enemies.remove(pos);
+    self.enemies.insert(to, nme);
+
+    let id = nme.id;
+    let rgr = self.ragdoll_ref(id);
+    let p1 = self.player_relative_coordinates(to.into());
+    let fade = 0.5 * BASE_ANIMATION_DURATION;
+    let moved = rgr.clone();
+    self.ui.animations.append_tween(Tween::sequence(vec![
+      tween(rgr.clone(), |r| &mut r.color.a, 1., 0., fade, FADE_EASE),
+      Tween::call(move || unsafe { moved.get().pos = p1; }),
+      tween(rgr, |r| &mut r.color.a, 0., 1., fade, FADE_EASE),
+    ])).label("boss teleport").reserve(id).reserve([pos, to]);
+    self.defer_set_hud(move |hud| hud.boss_pos = to).chain();
+    self.log_event("GhostWitch vanishes down the road".to_string(), to, MONSTER_COLOR);
+    true
+  }
+
+  // a puzzle ends as soon as its goal is met, or once it can't be
+  pub fn check_puzzle(&mut self) {
+    let Some(goal) = self.puzzle.as_ref().map(|p| p.goal) else { return; };
+    if self.player_defeat || self.player_victory { return; }
+    if goal == PuzzleGoal::Clear && self.enemies.len() == 0 {
+      self.sealed.puzzle_solved = true;
+    }
+    if self.sealed.puzzle_solved {
+      self.player_victory = true;
+      self.log_event("Puzzle solved!".to_string(), self.player_pos, YELLOW).chain();
+      self.ui.animations.sync();
+      self.defer_set_hud(|hud| hud.victory = true).chain();
+      return;
+    }
+    let boss_gone = !self.enemies.iter().any(|(_, nme)| nme.t == EnemyType::GhostWitch);
+    let failure = match goal {
+      PuzzleGoal::Close { .. } if self.player_tiles < 1 => "Out of tiles",
+      PuzzleGoal::Boss { .. } if boss_gone => "The GhostWitch fell too late",
+      _ => return,
+    };
+    self.player_defeat = true;
+    self.log_event(failure.to_string(), self.player_pos, GRAY).chain();
+    self.defer_set_hud(|hud| hud.defeat = true).chain();
+  }
+
+  pub fn puzzle_result(&self) -> PuzzleResult {
+    PuzzleResult {
+      tiles_placed: self.sealed.score_tiles_placed,
+      hp: self.player_hp,
+      turns: self.sealed.director.turn,
+    }
+  }
+
+  // endless runs: the last blow sends the boss away for a while
+  pub fn banish_boss(&mut self, pos: Position) {
+    self.sealed.boss_respawn = Some(BOSS_RESPAWN_TURNS);
+    let text = format!("GhostWitch banished for {} turns", BOSS_RESPAWN_TURNS);
+    self.log_event(text, pos, MONSTER_COLOR).chain();
+  }
+
+  // counts down to the boss returning somewhere far from the player
+  pub fn tick_boss_respawn(&mut self) {
+    let Some(turns) = self.sealed.boss_respawn else { return; };
+    if turns > 1 {
+      self.sealed.boss_respawn = Some(turns - 1);
+      return;
+    }
+    let mut spots: Vec<Position> = BOARD_RECT.iter().filter(|&p| {
+      self.sealed.board[p] != Tile::default()
+        && torus_max_norm(BOARD_RECT, p - self.player_pos) >= BOSS_RESPAWN_DISTANCE
+        && !self.enemies.contains_key(p)
+        && !self.quests.contains_key(p)
+        && !self.prizes.contains_key(p)
+    }).collect();
+    // nowhere far enough yet, try again next turn
+    if spots.is_empty() { return; }
+    shuffle(&mut spots, &mut self.rng);
+    let to = spots[0];
+    self.sealed.boss_respawn = None;
+    self.sealed.boss_cooldown = BOSS_COOLDOWN;
+    self.num_bosses = NUM_BOSSES;
+    self.spawn_enemy(EnemyType::GhostWitch, to);
+    self.defer_set_hud(move |hud| {
+      hud.bosses = NUM_BOSSES;
+      hud.boss_pos = to;
+      hud.boss_phase = BossPhase::Dormant;
+    }).chain();
+    self.log_event("The GhostWitch returns!".to_string(), to, MONSTER_COLOR).chain();
+  }
+
+  pub fn fire_shot(&mut self, nme: Enemy, from: Position, dir: Dir4, speed: f64) {
+    let path = shot_path(&self.sealed.board, from, dir);
+    let hit = path.iter().copied().find(|&p| equivalent(p, self.player_pos));
+    let end = hit.or(path.last().copied()).unwrap_or(from);
+    let id = nme.id;
+    self.defer_set_hud(move |hud| { hud.aims.remove(&id); })
+      .reserve([id, PLAYER_UNIT_ID]);
+    self.launch_shot(from, end, dir, speed).chain();
+    if hit.is_some() {
+      self.add_hp(-nme.attack).chain();
+    }
+  }
+
+  // a preview of a monster turn taken right now
+  // the real turn comes after the player's move, tile and quest rolls and the
+  // boss have all used the rng, so a random move can land elsewhere; that's
+  // why every option is kept. the dmaps, sightings and behaviors are built
+  // fresh here so the live ones are left alone
+  pub fn update_intents(&mut self) {
+    let mut player_dmap = DijkstraMap::new(BOARD_RECT);
+    fill_dmap(&mut player_dmap, &self.sealed.board, vec![self.player_pos]);
+    let last_seen = self.remembered_sightings();
+    let mut behaviors = Behaviors::standard();
+    behaviors.prepare(&EnemyView {
+      board: &self.sealed.board,
+      enemies: &self.enemies,
+      quests: &self.quests,
+      prizes: &self.prizes,
+      player_pos: self.player_pos,
+      player_facing: self.player_facing,
+      player_dmap: &player_dmap,
+      player_fov: &self.sealed.player_fov,
+      last_seen: &last_seen,
+      forest_escape_chance: forest_escape_chance(&self.perks),
+    });
+
+    let mut rng = self.rng.clone();
+    let mut enemies = self.enemies.clone();
+    let mut intents = vec![];
+    for (&pos, &nme) in self.enemies.iter() {
+      if self.sealed.frozen_turns > 0 { break; }
+      if nme.t == EnemyType::GhostWitch { continue; }
+      if nme.t == EnemyType::Sue {
+        // sues with a shot lined up stay put
+        let aiming = self.sealed.aims.contains_key(&nme.id)
+          || (self.sealed.player_fov.contains(pos)
+            && line_of_fire(&self.sealed.board, pos, self.player_pos).is_some());
+        if aiming { continue; }
+      }
+      let view = EnemyView {
+        board: &self.sealed.board,
+        enemies: &enemies,
+        quests: &self.quests,
+        prizes: &self.prizes,
+        player_pos: self.player_pos,
+        player_facing: self.player_facing,
+        player_dmap: &player_dmap,
+        player_fov: &self.sealed.player_fov,
+        last_seen: &last_seen,
+        forest_escape_chance: forest_escape_chance(&self.perks),
+      };
+      let options = behaviors.candidates(&view, pos, &mut rng);
+      if options.is_empty() { continue; }
+      let to = options[rng.next_u32() as usize % options.len()];
+      enemies.remove(pos);
+      enemies.insert(to, nme);
+      intents.push(Intent { from: pos, to, options });
+    }
+
+    let mut threats = 0;
+    for d in Dir4::list() {
+      let adj = self.player_pos + d.into();
+      if self.sealed.board[adj] == Tile::default() { continue; }
+      if enemies.contains_key(adj) { threats += 1; }
+    }
+
+    self.ui.intents = intents;
+    self.ui.threats = threats;
+    self.ui.intents_dirty = false;
   }
 
   pub fn move_enemy(&mut self, from: Position, to: Position, speed: f64) {
@@ -689,7 +1450,7 @@ impl SimulationState {
         let t0 = Vec2::from(from);
         let t1 = Vec2::from(to);
         let mid = (t0 + t1) / 2.;
-        self.ui.animations.append(empty_animation)
+        self.ui.animations.append(empty_animation).label("enemy move")
           .reserve([from,to])
           .reserve(nme.id);
         self.animate_unit_motion(nme.id, t0, mid, 0.5 * BASE_ANIMATION_DURATION / speed)
@@ -725,12 +1486,23 @@ impl SimulationState {
   }
 
   pub fn slay_enemy(&mut self, at: Position, dir: Dir4) {
+    self.fell_enemy(at, dir, true);
+  }
+
+  // slay without a fight, so it costs no health
+  pub fn blast_enemy(&mut self, at: Position, dir: Dir4) {
+    self.fell_enemy(at, dir, false);
+  }
+
+  fn fell_enemy(&mut self, at: Position, dir: Dir4, fought: bool) {
     let Some(nme) = self.enemies.remove(at) else { return; };
+    if self.sealed.aims.remove(&nme.id).is_some() {
+      let id = nme.id;
+      self.defer_set_hud(move |hud| { hud.aims.remove(&id); });
+    }
     // credit quests
     for (_, quest) in self.quests.iter_mut() {
-      if quest.target == nme.t && quest.quota > 0 {
-        quest.quota -= 1;
-      }
+      quest.credit_kill(nme.t);
     }
 
     // do animation
@@ -745,7 +1517,9 @@ impl SimulationState {
     self.defer_play_sound(xp_sound()).chain();
     self.animate_unit_fling(id, at.into(), velocity, 0.2)
       .require(id);
-    self.add_hp(-1).require(id);
+    if fought {
+      self.add_hp(-nme.attack).require(id);
+    }
     self.ui.animations.append(empty_animation)
       .require([id, PLAYER_UNIT_ID]);
     self.launch_particle(
@@ -759,6 +1533,29 @@ impl SimulationState {
     self.add_xp(1).chain();
   }
 
+  // the enemy at a position takes a blow it survives, leaving it as nme
+  pub fn strike_enemy(&mut self, at: Position, dir: Dir4, nme: Enemy) {
+    self.enemies.insert(at, nme);
+
+    // enemy is knocked back a little and hits back
+    let id = nme.id;
+    let p0: Vec2 = at.into();
+    let p1 = p0 + Vec2::from(dir) * 0.3;
+    self.ui.animations.append_empty(0.).reserve(
+      [id, PLAYER_UNIT_ID]
+    ).reserve(at);
+    self.animate_unit_motion(id, p0, p1, 0.05).chain();
+    let rgr = self.ragdoll_ref(id);
+    self.ui.animations.append(move |_| unsafe {
+      rgr.get().pips = nme.pips();
+      false
+    }).chain();
+    self.add_hp(-nme.attack).chain();
+    self.animate_unit_motion(id, p1, p0, 0.1).chain();
+    self.ui.animations.append(empty_animation)
+      .require([id, PLAYER_UNIT_ID]);
+  }
+
   pub fn add_xp(&mut self, amount: i64) -> &mut Animation {
     self.player_xp += amount;
     let hud = self.ui.hud.clone();
@@ -773,30 +1570,81 @@ impl SimulationState {
   }
   pub fn add_hp(&mut self, amount: i64) -> &mut Animation {
     let is_damage = amount < 0;
+    if is_damage {
+      for (_, quest) in self.quests.iter_mut() {
+        quest.credit_damage();
+      }
+    }
     self.player_hp += amount;
     if self.player_hp < self.score_min_hp {
       self.score_min_hp = self.player_hp;
     }
     let hudref = self.ui.hud.clone();
     let duration = 0.1;
-    self.ui.animations.append(move |time| unsafe {
-      let hud = hudref.get();
-      if is_damage { hud.hp_color = RED; }
-      let more = time.progress(duration) < 1.;
-      if !more {
+    let flash = hudref.clone();
+    self.ui.animations.append_tween(Tween::sequence(vec![
+      Tween::call(move || unsafe { if is_damage { flash.get().hp_color = RED; } }),
+      Tween::wait(duration),
+      Tween::call(move || unsafe {
+        let hud = hudref.get();
         hud.hp_color = WHITE;
         hud.hp += amount;
-      }
-      more
-    })
+      }),
+    ]))
+  }
+
+  pub fn add_monster_turns(&mut self, amount: i64) -> &mut Animation {
+    self.monster_turns += amount;
+    self.defer_set_hud(move |hud| hud.turns += amount)
   }
 
-  pub fn add_monster_turns(&mut self, amount: i64) -> &mut Animation {
-    self.monster_turns += amount;
-    self.defer_set_hud(move |hud| hud.turns += amount)
+  // plays out a step in this direction the same way the fight code does,
+  // without touching anything. None if the step doesn't start a fight
+  pub fn preview_combat(&self, dir: Dir4) -> Option<CombatPreview> {
+    if !self.in_combat() { return None; }
+    let target = self.player_pos + dir.into();
+    let mut enemies = self.enemies.clone();
+    let mut hp = self.player_hp;
+    let mut slain: Vec<EnemyType> = vec![];
+    let dead = |hp: i64| self.would_die(hp);
+
+    let mut crowd_fights = true;
+    if let Some(&boss @ Enemy { t: EnemyType::GhostWitch, .. }) = enemies.get(target) {
+      let mut remaining = self.num_bosses;
+      let floor = BossPhase::of(remaining).floor();
+      while remaining > floor {
+        hp -= boss.attack;
+        remaining -= 1;
+        slain.push(boss.t);
+        if dead(hp) { break; }
+      }
+      crowd_fights = remaining <= 1;
+    }
+
+    let crowd = self.calculate_crowd(target);
+    if crowd.is_empty() && slain.is_empty() { return None; }
+    if crowd_fights {
+      let blows = fight_crowd(&mut enemies, &crowd, target, &mut hp, dead);
+      slain.extend(blows.iter().filter_map(Blow::slain));
+    }
+
+    let mut quests_completed = 0;
+    for (_, quest) in self.quests.iter() {
+      let Objective::Slay { target, quota } = quest.objective else { continue; };
+      if quest.done { continue; }
+      let kills = slain.iter().filter(|&&t| t == target).count() as u64;
+      if kills >= quota { quests_completed += 1; }
+    }
+
+    Some(CombatPreview {
+      damage: self.player_hp - hp,
+      slain: slain.len(),
+      quests_completed,
+      lethal: dead(hp),
+    })
   }
 
-  pub fn in_combat(&mut self) -> bool {
+  pub fn in_combat(&self) -> bool {
     let mut in_combat = false;
     for d in Dir4::list() {
       let adj = self.player_pos + d.into();
@@ -807,7 +1655,17 @@ impl SimulationState {
     in_combat
   }
 
+  // whether a step this way gets out of a fight without fighting
+  pub fn can_slip(&self, dir: Dir4) -> bool {
+    self.is_road_dir(dir)
+      || (self.class.rules().forest_stride && self.is_terrain_dir(dir, Terrain::Forest))
+  }
+
   pub fn is_road_dir(&self, dir: Dir4) -> bool {
+    self.is_terrain_dir(dir, Terrain::Road)
+  }
+
+  pub fn is_terrain_dir(&self, dir: Dir4, terrain: Terrain) -> bool {
     // two cases:
     // 1) there is an existing road here we can take
     // 2) there is a half road here, with the other half
@@ -815,10 +1673,10 @@ impl SimulationState {
     // either way, the check for the first half of the road is the same
     let target = self.player_pos + dir.into();
     let opp = dir.opposite();
-    let first_half = Terrain::Road ==
+    let first_half = terrain ==
       self.sealed.board[self.player_pos].contents[dir.index()];
 
-    let second_half = Terrain::Road ==
+    let second_half = terrain ==
       if self.sealed.board[target] == Tile::default() {
         self.player_current_tile().contents[opp.index()]
       } else {
@@ -842,6 +1700,7 @@ impl SimulationState {
         color: LIGHTGRAY,
         img: HERO,
         dead: false,
+        pips: 0,
       });
       self.ui.ragdolls.insert(unit_id, rgr.clone());
       rgr
@@ -854,6 +1713,7 @@ impl SimulationState {
         color: Color{a: 0., ..RED},
         img: UNKNOWN_ENEMY,
         dead: false,
+        pips: 0,
       });
       self.ui.ragdolls.insert(unit_id, rgr.clone());
       let result = rgr.clone();
@@ -867,29 +1727,20 @@ impl SimulationState {
 
   pub fn animate_unit_fling(&mut self, u: UnitId, p0: Vec2, velocity: Vec2, duration: Seconds) -> &mut Animation {
     let uref = self.ragdoll_ref(u);
-    self.ui.animations.append(move |time| {
-      let progress = time.progress(duration);
-      unsafe {
-        uref.get().pos = p0 + velocity * (time.elapsed as f32);
-        if progress >= 1. {
-          uref.get().dead = true;
-        }
-      }
-      progress < 1.
-    })
+    let p1 = p0 + velocity * duration as f32;
+    self.ui.animations.append_tween(Tween::sequence(vec![
+      tween(uref.clone(), |r| &mut r.pos, p0, p1, duration, UNIT_FLING_EASE),
+      Tween::call(move || unsafe { uref.get().dead = true; }),
+    ])).label("unit fling")
   }
 
   pub fn animate_unit_motion(&mut self, u: UnitId, p0: Vec2, p1: Vec2, duration: Seconds) -> &mut Animation {
     let prc0 = self.player_relative_coordinates(p0);
     let prc1 = self.player_relative_coordinates(p1);
     let uref = self.ragdoll_ref(u);
-    self.ui.animations.append(move |time| {
-      let c = time.progress(duration);
-        unsafe {
-          uref.get().pos = c * prc1 + (1.-c) * prc0;
-        }
-      c < 1.
-    })
+    self.ui.animations.append_tween(
+      tween(uref, |r| &mut r.pos, prc0, prc1, duration, UNIT_MOTION_EASE)
+    ).label("unit motion")
   }
 
   pub fn launch_tile(
@@ -908,19 +1759,16 @@ impl SimulationState {
 
     self.ui.flying_tiles.push(p.clone());
 
-    let duration = 0.15;
-    self.ui.animations.append(move |time: Time| {
-      let c = time.progress(duration);
-      let camera_focus = *cr;
-      let target_board = Vec2::from(to - camera_focus);
-      let target_screen_pos = DISPLAY_GRID.rect(target_board).center();
-      unsafe{
-        let it = p.get();
-        it.pos = origin_pos * (1. - c) + target_screen_pos * c;
-        it.dead = c >= 1.;
-      }
-      !p.dead
-    })
+    // the target follows the camera while the tile is in flight
+    let flying = p.clone();
+    self.ui.animations.append_tween(Tween::sequence(vec![
+      Tween::new(TILE_LAUNCH_DURATION, TILE_LAUNCH_EASE, move |c| unsafe {
+        let target_board = Vec2::from(to - *cr);
+        let target_screen_pos = DISPLAY_GRID.rect(target_board).center();
+        flying.get().pos = Vec2::lerp(origin_pos, target_screen_pos, c);
+      }),
+      Tween::call(move || unsafe { p.get().dead = true; }),
+    ])).label("launch tile")
   }
 
   pub fn launch_particle(
@@ -936,7 +1784,8 @@ impl SimulationState {
       pos: Vec2{x: f32::MAX, y: f32::MAX},
       img,
       color: INVISIBLE,
-      dead: false
+      dead: false,
+      pips: 0,
     });
 
     let v: Ref<Vec2> = Ref::new({
@@ -968,12 +1817,56 @@ impl SimulationState {
         it.dead = to.distance(it.pos) < 20.;
       }
       !p.dead
-    })
+    }).label("particle")
+  }
+
+  pub fn launch_shot(&mut self, from: Position, to: Position, dir: Dir4, speed: f64) -> &mut Animation {
+    let p = Ref::new(Particle {
+      pos: Vec2{x: f32::MAX, y: f32::MAX},
+      img: arrow_img(dir),
+      color: RED,
+      dead: false,
+      pips: 0,
+    });
+    self.ui.particles.push(p.clone());
+    let p0 = self.player_relative_coordinates(Vec2::from(from));
+    let p1 = p0 + Vec2::from(to - from);
+    let duration = 0.04 * (p1 - p0).length() as f64 / speed;
+    let cr = self.ui.camera_ref.clone();
+
+    // the shot follows the camera while it is in flight
+    let flying = p.clone();
+    self.ui.animations.append_tween(Tween::sequence(vec![
+      Tween::new(duration, SHOT_EASE, move |c| unsafe {
+        let camera_focus = Vec2::from(*cr);
+        flying.get().pos = DISPLAY_GRID.rect(Vec2::lerp(p0, p1, c) - camera_focus).center();
+      }),
+      Tween::call(move || unsafe { p.get().dead = true; }),
+    ])).label("shot")
+  }
+
+  pub fn defeat_player(&mut self, away_from: Position) {
+    self.player_defeat = true;
+    let away = torus_offset(BOARD_RECT, self.player_pos - away_from);
+    let dirvec: Vec2 = Vec2::from(away).normalize_or_zero();
+    let mut velocity: Vec2 = dirvec * 3.;
+    velocity.x += (self.rng.next_u32() % 1000) as f32 / 1000.;
+    velocity.y += (self.rng.next_u32() % 1000) as f32 / 1000.;
+    velocity *= 2.;
+    self.animate_unit_fling(
+      PLAYER_UNIT_ID,
+      self.player_pos.into(),
+      velocity,
+      2.).reserve(PLAYER_UNIT_ID);
+    self.defer_set_hud(|hud| hud.defeat = true).chain();
   }
 
   pub fn move_player(&mut self, to: Position) {
     let from = self.player_pos;
     self.player_pos = to;
+    if let Ok(dir) = Dir4::try_from(to - from) {
+      self.player_facing = dir;
+    }
     self.ui.hud.desire_path.push(to);
 
     self.animate_unit_motion(PLAYER_UNIT_ID, from.into(), to.into(), BASE_ANIMATION_DURATION.into())
@@ -1031,6 +1924,276 @@ impl SimulationState {
 
 
 
+// the title screen, with the high score table a key away
+// returns the mode of the run the player starts
+async fn title_screen(display: &Display, bgm: &mut BGM, scores: &ScoreTable) -> GameMode {
+  let mut showing_scores = false;
+  let mut sort = 0;
+  loop {
+    set_camera(&display.render_to);
+    clear_background(BLACK);
+    if showing_scores {
+      draw_score_table(display, scores, ScoreField::ALL[sort]);
+    } else {
+      let mut y = 400.;
+      for (text, font_size, color) in [
+        ("TILER'S ADVENTURE", 96, WHITE),
+        ("[1] New run", 64, YELLOW),
+        ("[2] Endless run", 64, WHITE),
+        ("[3] Puzzles", 64, WHITE),
+        ("[4] High scores", 64, WHITE),
+      ] {
+        let metrics = measure_text(text, None, font_size, 1.);
+        draw_text(text, 0.5 * (display.dim.x - metrics.width), y, font_size as f32, color);
+        y += metrics.height + 60.;
+      }
+    }
+    display.present();
+    next_frame().await;
+
+    bgm.poll();
+    let Some(input) = get_input() else { continue; };
+    if input == Input::Mute { bgm.mute(); }
+    let n = ScoreField::ALL.len();
+    if showing_scores {
+      match input {
+        Input::Dir(Dir4::Left) => sort = (sort + n - 1) % n,
+        Input::Dir(Dir4::Right) => sort = (sort + 1) % n,
+        Input::LevelUp | Input::Discard => showing_scores = false,
+        _ => {}
+      }
+    } else {
+      match input {
+        Input::Choose(0) | Input::LevelUp => return GameMode::Standard,
+        Input::Choose(1) => return GameMode::Endless,
+        Input::Choose(2) => return GameMode::Puzzle,
+        Input::Choose(3) => showing_scores = true,
+        _ => {}
+      }
+    }
+  }
+}
+
+fn draw_score_table(display: &Display, scores: &ScoreTable, sort: ScoreField) {
+  const ROWS: usize = 15;
+  let font_size = 30.;
+  let row_height = 46.;
+  let margin = 15.;
+  let column = (display.dim.x - 2. * margin) / ScoreField::ALL.len() as f32;
+  let title = "High Scores";
+  let metrics = measure_text(title, None, 64, 1.);
+  draw_text(title, 0.5 * (display.dim.x - metrics.width), 120., 64., WHITE);
+
+  let mut y = 220.;
+  for (i, field) in ScoreField::ALL.iter().enumerate() {
+    let color = if *field == sort { YELLOW } else { GRAY };
+    draw_text(field.name(), margin + column * i as f32, y, font_size, color);
+  }
+  y += row_height;
+  if scores.records.is_empty() {
+    draw_text("No runs yet", margin, y, font_size, WHITE);
+  }
+  for record in scores.sorted_by(sort).iter().take(ROWS) {
+    let color = if record.won { WHITE } else { LIGHTGRAY };
+    for (i, field) in ScoreField::ALL.iter().enumerate() {
+      draw_text(&field.show(record), margin + column * i as f32, y, font_size, color);
+    }
+    y += row_height;
+  }
+  let hint = "[A]/[D] to sort, [Z] to go back. * marks a defeat";
+  let metrics = measure_text(hint, None, font_size as u16, 1.);
+  draw_text(hint, 0.5 * (display.dim.x - metrics.width), y + 2. * margin, font_size, GRAY);
+}
+
+// every queued animation, two lines each: what it is and how far along,
+// then the positions and units its lock claims
+fn draw_animation_debug(display: &Display, animations: &AnimationQueue) {
+  let font_size = 24.;
+  let row_height = 26.;
+  let margin = 15.;
+  let infos = animations.inspect();
+  let rows = ((display.dim.y - 2. * margin) / (2. * row_height)) as usize;
+  let shown = infos.len().min(rows.saturating_sub(1));
+  let h = (2 * shown + 1) as f32 * row_height + margin;
+  draw_rectangle(0., 0., display.dim.x, h, Color{a: 0.8, ..BLACK});
+
+  let mut y = margin + row_height;
+  let title = format!("{} animations (F3 to hide)", infos.len());
+  draw_text(&title, margin, y, font_size, YELLOW);
+  for (i, info) in infos.iter().enumerate().take(shown) {
+    y += row_height;
+    let color = match info.state {
+      AnimationState::Active => WHITE,
+      AnimationState::Finished => GRAY,
+      AnimationState::Waiting | AnimationState::Chain => ORANGE,
+    };
+    let blocked = match info.blocked_by {
+      Some(j) => format!("  blocked by #{} {}", j, infos[j].label.unwrap_or("")),
+      None => String::new(),
+    };
+    let line = format!("#{} {} {:?} {:.2}s{}",
+      i, info.label.unwrap_or("?"), info.state, info.elapsed, blocked);
+    draw_text(&line, margin, y, font_size, color);
+    y += row_height;
+    let locks = format!("    require {}  reserve {}",
+      info.lock.require.describe(), info.lock.reserve.describe());
+    draw_text(&locks, margin, y, font_size, LIGHTGRAY);
+  }
+}
+
+// the screens between runs: the title, then class or puzzle select
+#[allow(clippy::too_many_arguments)]
+async fn new_run(
+  display: &Display,
+  bgm: &mut BGM,
+  sounds: &Map<Path, Rc<Sound>>,
+  profile: &Profile,
+  scores: &ScoreTable,
+  puzzles: &[Puzzle],
+  bests: &PuzzleBests,
+  last: PlayerClass,
+) -> SimulationState {
+  loop {
+    match title_screen(display, bgm, scores).await {
+      GameMode::Puzzle => {
+        // backing out of the puzzle list returns to the title
+        let Some(i) = select_puzzle(display, bgm, puzzles, bests).await else { continue; };
+        return SimulationState::new_puzzle(sounds, &puzzles[i]);
+      }
+      mode => {
+        let class = select_class(display, bgm, profile, last).await;
+        return SimulationState::new(sounds, class, mode);
+      }
+    }
+  }
+}
+
+// the level select for puzzle mode, None to go back
+async fn select_puzzle(
+  display: &Display,
+  bgm: &mut BGM,
+  puzzles: &[Puzzle],
+  bests: &PuzzleBests,
+) -> Option<usize> {
+  let mut cursor = 0;
+  loop {
+    set_camera(&display.render_to);
+    clear_background(BLACK);
+    let margin = 15.;
+    let font_size = 64;
+    let small_font_size = 40;
+    let mut y = 200.;
+    let title = "Choose a puzzle";
+    let metrics = measure_text(title, None, font_size, 1.);
+    draw_text(title, 0.5 * (display.dim.x - metrics.width), y, font_size as f32, WHITE);
+    y += metrics.height + 4. * margin;
+    if puzzles.is_empty() {
+      draw_text("No puzzles found", 0.3 * display.dim.x, y, font_size as f32, GRAY);
+      y += 60. + margin;
+    }
+    for (i, puzzle) in puzzles.iter().enumerate() {
+      let color = if i == cursor { YELLOW } else { WHITE };
+      let x = 0.3 * display.dim.x;
+      draw_text(&puzzle.name, x, y, font_size as f32, color);
+      let best = match bests.results.get(&puzzle.id) {
+        Some(r) => format!("Best: {} tiles, {} HP", r.tiles_placed, r.hp),
+        None => "Unsolved".to_string(),
+      };
+      draw_text(&best, 0.6 * display.dim.x, y, small_font_size as f32, color);
+      y += 50.;
+      draw_text(&puzzle.goal.describe(), x + 2. * margin, y, small_font_size as f32, GRAY);
+      y += 60. + margin;
+    }
+    let hint = "[W]/[S] to browse, [Z] to start, [X] to go back";
+    let metrics = measure_text(hint, None, small_font_size, 1.);
+    draw_text(hint, 0.5 * (display.dim.x - metrics.width), y + 2. * margin,
+      small_font_size as f32, GRAY);
+    display.present();
+    next_frame().await;
+
+    bgm.poll();
+    let n = puzzles.len().max(1);
+    match get_input() {
+      Some(Input::Dir(Dir4::Up)) => cursor = (cursor + n - 1) % n,
+      Some(Input::Dir(Dir4::Down)) => cursor = (cursor + 1) % n,
+      Some(Input::LevelUp) if cursor < puzzles.len() => return Some(cursor),
+      Some(Input::Discard) => return None,
+      Some(Input::Mute) => bgm.mute(),
+      _ => {}
+    }
+  }
+}
+
+// the class select screen shown before each run
+async fn select_class(
+  display: &Display,
+  bgm: &mut BGM,
+  profile: &Profile,
+  last: PlayerClass,
+) -> PlayerClass {
+  let mut cursor = CLASSES.iter().position(|&c| c == last).unwrap_or(0);
+  loop {
+    set_camera(&display.render_to);
+    clear_background(BLACK);
+    let margin = 15.;
+    let font_size = 64;
+    let small_font_size = 40;
+    let mut y = 200.;
+    let title = "Choose your class";
+    let metrics = measure_text(title, None, font_size, 1.);
+    draw_text(title, 0.5 * (display.dim.x - metrics.width), y, font_size as f32, WHITE);
+    y += metrics.height + 4. * margin;
+    for (i, &c) in CLASSES.iter().enumerate() {
+      let rules = c.rules();
+      let locked = profile.locked(c);
+      let color = match (i == cursor, locked.is_some()) {
+        (true, _) => YELLOW,
+        (false, true) => DARKGRAY,
+        (false, false) => WHITE,
+      };
+      let text = format!("[{}] {}", i + 1, c.name());
+      let stats = format!("HP {}  Tiles {}",
+        STARTING_HP + rules.hp, STARTING_TILES + rules.tiles);
+      let x = 0.3 * display.dim.x;
+      draw_text(&text, x, y, font_size as f32, color);
+      draw_text(&stats, 0.6 * display.dim.x, y, small_font_size as f32, color);
+      y += 50.;
+      let description = match locked {
+        Some(requirement) => format!("Locked: {}", requirement),
+        None => c.description().to_string(),
+      };
+      draw_text(&description, x + 2. * margin, y, small_font_size as f32, GRAY);
+      y += 60. + margin;
+    }
+    let hint = "[W]/[S] to browse, [Z] to start";
+    let metrics = measure_text(hint, None, small_font_size, 1.);
+    draw_text(hint, 0.5 * (display.dim.x - metrics.width), y + 2. * margin,
+      small_font_size as f32, GRAY);
+    let record = format!("Runs {}  Wins {}  GhostWitches slain {}  Largest region {}",
+      profile.runs, profile.wins, profile.bosses_slain, profile.largest_region);
+    let metrics = measure_text(&record, None, small_font_size, 1.);
+    draw_text(&record, 0.5 * (display.dim.x - metrics.width), y + 8. * margin,
+      small_font_size as f32, GRAY);
+    display.present();
+    next_frame().await;
+
+    bgm.poll();
+    match get_input() {
+      Some(Input::Dir(Dir4::Up)) => cursor = (cursor + CLASSES.len() - 1) % CLASSES.len(),
+      Some(Input::Dir(Dir4::Down)) => cursor = (cursor + 1) % CLASSES.len(),
+      Some(Input::Choose(i)) if i < CLASSES.len() => {
+        cursor = i;
+        if profile.locked(CLASSES[i]).is_none() { return CLASSES[i]; }
+      }
+      Some(Input::LevelUp) if profile.locked(CLASSES[cursor]).is_none() => {
+        return CLASSES[cursor];
+      }
+      Some(Input::Mute) => bgm.mute(),
+      _ => {}
+    }
+  }
+}
+
 #[macroquad::main("7drl")]
 async fn main() {
   debug!("This is a debug message");
@@ -1060,7 +2223,20 @@ async fn main() {
   let display_dim: Vec2 = DISPLAY_GRID.dim();
   let mut display = Display::new(resources, display_dim);
 
-  let mut sim = SimulationState::new(&sounds);
+  let mut profile = Profile::load();
+  let mut scores = ScoreTable::load();
+  let puzzles = load_puzzles();
+  let mut bests = PuzzleBests::load();
+  let mut sim = new_run(&display, &mut bgm, &sounds, &profile, &scores,
+    &puzzles, &bests, PlayerClass::Wanderer).await;
+  // whether this run has made it into the profile and score table yet
+  let mut recorded = false;
+  // where this run placed in the score table
+  let mut rank: Option<usize> = None;
+  // whether a solved puzzle beat its best result
+  let mut new_best = false;
+  // classes this run unlocked, for the victory screen
+  let mut unlocked: Vec<PlayerClass> = vec![];
 
   let mut debug_draw = false;
 
@@ -1074,16 +2250,47 @@ async fn main() {
     let mut inputdir: Option<Dir4> = None;
 
     if let Some(input) = get_input() {
+      sim.ui.intents_dirty = true;
       if sim.ui.hud.defeat || sim.ui.hud.victory {
-        sim = SimulationState::new(&sounds);
+        sim = new_run(&display, &mut bgm, &sounds, &profile, &scores,
+          &puzzles, &bests, sim.class).await;
+        recorded = false;
+        rank = None;
+        new_best = false;
+        unlocked.clear();
         next_frame().await;
         continue;
       }
       match input {
         Input::Mute => bgm.mute(),
+        // no moving while a perk is being picked
+        Input::Dir(_) if sim.showing_perks => {}
         Input::Dir(dir) => {
+          sim.ui.log_cursor = None;
           inputdir = Some(dir)
         }
+        Input::ToggleFog => {
+          sim.ui.fog_of_war = !sim.ui.fog_of_war;
+        }
+        Input::ToggleIntents => {
+          sim.ui.show_intents = !sim.ui.show_intents;
+        }
+        Input::ToggleAnimDebug => {
+          sim.ui.show_anim_debug = !sim.ui.show_anim_debug;
+        }
+        Input::SkipAnimations => {
+          // runs the hud updates too, so the hud catches up with the sim
+          sim.ui.animations.finish_all();
+        }
+        Input::CycleLog => {
+          // step backwards through the log, then back to the player
+          let n = sim.ui.hud.log.len();
+          sim.ui.log_cursor = match sim.ui.log_cursor {
+            None if n > 0 => Some(n - 1),
+            Some(i) if i > 0 => Some(i - 1),
+            _ => None,
+          };
+        }
         Input::Rotate1 => {
           sim.transform_tile(D8::R1);
         }
@@ -1094,21 +2301,53 @@ async fn main() {
           if sim.player_tiles > 0 {
             sim.sealed.next_quest = None;
             sim.next_tile();
+            if sim.has_perk(Perk::Juggler) && !sim.fixed_tiles() {
+              sim.add_tiles(1).chain();
+            }
           }
         }
         Input::LevelUp =>
-          if sim.player_xp >= sim.player_xp_next() {
-            sim.add_xp(-sim.player_xp_next());
-            sim.player_hp_max += 1;
-            let to = sim.ui.layout[&HudItem::Hp].center();
-            sim.defer_play_sound(LEVEL_UP_SOUND);
-            sim.launch_particle(sim.player_pos, to,
-              HEART, RED,
-              3., 0.02
-            ).chain();
-            sim.full_heal().chain();
-            sim.player_level += 1;
+          if sim.showing_perks {
+            // changed our mind, keep the XP and the offer for later
+            sim.showing_perks = false;
+          } else if !sim.perk_choice.is_empty() {
+            sim.showing_perks = true;
+          } else if sim.player_xp >= sim.player_xp_next() {
+            sim.perk_choice = roll_perks(&sim.perks, &mut sim.rng);
+            sim.showing_perks = !sim.perk_choice.is_empty();
+          }
+        Input::Choose(i) =>
+          if sim.showing_perks {
+            if i < sim.perk_choice.len() {
+              let perk = sim.perk_choice[i];
+              sim.perk_choice.clear();
+              sim.showing_perks = false;
+              sim.take_perk(perk);
+            }
+          } else if i < sim.inventory.len() {
+            if sim.can_use_prize(sim.inventory[i]) {
+              let prize = sim.inventory.remove(i);
+              sim.use_prize(prize);
+            } else {
+              let text = format!("{} needs the fog of war on", sim.inventory[i].name());
+              sim.log_event(text, sim.player_pos, GRAY).chain();
+            }
+          }
+      }
+    }
+
+    if is_mouse_button_pressed(MouseButton::Left) {
+      // clicking a log entry pans to it, clicking it again pans back
+      let click = display.screen_to_display(mouse_position().into());
+      if let Some(panel) = sim.ui.layout.get(&HudItem::Log) {
+        if panel.contains(click) {
+          let window = log_window(sim.ui.hud.log.len(), sim.ui.log_cursor);
+          let line = ((click.y - panel.y) / LOG_LINE_HEIGHT) as usize;
+          let i = window.start + line;
+          if i < window.end {
+            sim.ui.log_cursor = if sim.ui.log_cursor == Some(i) { None } else { Some(i) };
           }
+        }
       }
     }
 
@@ -1125,55 +2364,87 @@ async fn main() {
       // do combat
       if sim.in_combat() {
         let mut defeated_boss = false;
+        let mut boss_standing = false;
         if let Some(Enemy { t: EnemyType::GhostWitch, .. }) = sim.enemies.get(target) {
+          let bosses_before = sim.num_bosses;
+          let phase_before = BossPhase::of(sim.num_bosses);
           let mut speed_mul: f64 = 1.;
-          while sim.num_bosses > 1 {
+          // each blow takes the boss down to the end of its current phase
+          while sim.num_bosses > phase_before.floor() {
             let id = sim.enemies.get(target).unwrap().id;
             let delay = BASE_ANIMATION_DURATION/speed_mul;
             sim.ui.animations.append_empty(delay)
               .reserve(id);
             sim.slay_enemy(target, playermove);
             sim.num_bosses -= 1;
+            sim.sealed.score_bosses_slain += 1;
             sim.defer_set_hud(|hud| hud.bosses -= 1).reserve(id);
             sim.spawn_enemy(EnemyType::GhostWitch, target);
             sim.set_enemy_alerts(true);
             speed_mul += 0.5;
             if sim.player_dead() { break; }
           }
-          if !sim.player_dead() {
+          let slain = bosses_before - sim.num_bosses;
+          if slain > 0 {
+            let text = format!("GhostWitch slain x{} ({} left)", slain, sim.num_bosses);
+            sim.log_event(text, target, MONSTER_COLOR).chain();
+          }
+          let phase = BossPhase::of(sim.num_bosses);
+          if !sim.player_dead() && sim.num_bosses > 1 {
+            boss_standing = true;
+            player_moved = true;
+            if phase != phase_before {
+              sim.sealed.boss_cooldown = BOSS_COOLDOWN;
+              sim.log_event(phase.announcement().to_string(), target, MONSTER_COLOR).chain();
+              sim.defer_set_hud(move |hud| {
+                hud.boss_phase = phase;
+                hud.phase_banner = 3.;
+              }).chain();
+            }
+          } else if !sim.player_dead() {
             defeated_boss = true;
           }
         }
         let crowd: Map<Position, u8> = sim.calculate_crowd(target);
-        if crowd.len() > 0 { // fight!
+        if boss_standing {
+          // the boss is still up, nothing else fights this turn
+        } else if !crowd.is_empty() { // fight!
           player_moved = true;
+          // the fight is settled first, then played out blow by blow
+          let mut enemies = sim.enemies.clone();
+          let mut hp = sim.player_hp;
+          let blows = fight_crowd(&mut enemies, &crowd, target, &mut hp, |hp| sim.would_die(hp));
           let mut speed_mul: f64 = 1.;
-          while sim.enemies.contains_key(target) {
-            if sim.player_dead() { break; }
+          for blow in blows {
             speed_mul += 0.5;
-            sim.slay_enemy(target, playermove);
-            // enemies behind move up
-            let mut vacated = target;
-            let mut dist = 0;
-            'scooch: loop {
-              for d in Dir4::list() {
-                let neighbor = vacated + d.into();
-                if let Some(&dist2) = crowd.get(&neighbor) {
-                  // enemies only want to scooch closer
-                  if dist2 <= dist { continue; }
-                  if sim.enemies.contains_key(neighbor) {
-                    sim.move_enemy(neighbor, vacated, speed_mul);
-                    vacated = neighbor;
-                    dist = dist2;
-                    continue 'scooch;
-                  }
+            match blow {
+              Blow::Hit(nme) => sim.strike_enemy(target, playermove, nme),
+              Blow::Slain(_, moves) => {
+                sim.slay_enemy(target, playermove);
+                // enemies behind move up
+                for (from, to) in moves {
+                  sim.move_enemy(from, to, speed_mul);
                 }
               }
-              break;
             }
           }
 
-          let won = defeated_boss && !sim.player_dead();
+          let vanquished = defeated_boss && !sim.player_dead();
+          if vanquished {
+            sim.sealed.score_bosses_slain += 1;
+          }
+          let won = vanquished && sim.mode.boss_ends_run();
+          sim.player_victory = won;
+          if won {
+            sim.log_event("GhostWitch vanquished!".to_string(), target, MONSTER_COLOR).chain();
+          } else if vanquished && sim.mode == GameMode::Endless {
+            sim.banish_boss(target);
+          } else if vanquished {
+            sim.log_event("GhostWitch vanquished!".to_string(), target, MONSTER_COLOR).chain();
+            if let Some(PuzzleGoal::Boss { hp }) = sim.puzzle.as_ref().map(|p| p.goal) {
+              if sim.player_hp >= hp { sim.sealed.puzzle_solved = true; }
+            }
+          }
           sim.ui.animations.sync();
           sim.defer_set_hud(move |hud| hud.victory = won).chain();
         } else { // nobody in this spot to fight
@@ -1183,7 +2454,7 @@ async fn main() {
 
 
       let using_road = sim.is_road_dir(playermove);
-      can_move = can_move && (!needs_road || using_road);
+      can_move = can_move && (!needs_road || sim.can_slip(playermove));
       can_move = can_move && (!target_empty || sim.tile_compatibility(target, sim.player_current_tile()) > 0);
       if sim.player_tiles < 1  && sim.sealed.board[target] == Tile::default() {
         can_move = false;
@@ -1206,6 +2477,11 @@ async fn main() {
           t0 == Terrain::River
             && t1 == Terrain::River
         };
+        // a road on either end of the step bridges the river
+        let bridged = sim.class.rules().ford_rivers
+          || (sim.has_perk(Perk::Bridgewright)
+            && (sim.sealed.board[sim.player_pos].count(Terrain::Road) > 0
+              || sim.sealed.board[target].count(Terrain::Road) > 0));
 
         // try to place tile
         if sim.sealed.board[target] == Tile::default() && sim.player_tiles > 0 {
@@ -1244,7 +2520,7 @@ async fn main() {
                     is_matched = false;
                 }
               }
-              if is_matched {
+              if is_matched && !sim.fixed_tiles() {
                 let to = sim.ui.layout[&HudItem::Tile].center();
                 sim.ui.animations.append_empty(0.).require(PLAYER_UNIT_ID);
 
@@ -1286,7 +2562,7 @@ async fn main() {
             sim.reward_completed_region(regionid);
           }
         } else { // we stepped on an existing tile
-          if (target_is_slow || edge_is_slow) && !using_road {
+          if (target_is_slow || edge_is_slow) && !using_road && !bridged {
             let to = sim.ui.layout[&HudItem::SpeedPenalty].center();
             sim.ui.animations.append(empty_animation).require(target);
             sim.launch_particle(target, to,
@@ -1301,64 +2577,22 @@ async fn main() {
         // clear monster alerts
         sim.set_enemy_alerts(false);
         sim.move_player(target);
+        sim.update_player_fov();
         player_moved = true;
         //debug!("player: {:?}", sim.player_pos);
 
-        { // Quest reward, spawn quest items
-          let mut fulfilled_quests: WrapMap<Quest> = WrapMap::new(BOARD_RECT);
-          for (&p, &q) in sim.quests.clone().iter() {
-            if q.quota < 1 && sim.player_hp > 0 {
-              let distance = torus_max_norm(BOARD_RECT, p - target);
-              if distance >= 4 { continue; }
-
-              fulfilled_quests.insert(p, q);
-              sim.quests.remove(p);
-              sim.prizes.insert(p, Prize::Heal);
-              let to = sim.ui.layout[&HudItem::Tile].center();
-              for i in 0..(QUEST_REWARD as u8) {
-                let delay = f64::from(i)* 0.7 * BASE_ANIMATION_DURATION ;
-                sim.ui.animations.append_empty(0.).require(PLAYER_UNIT_ID);
-                sim.ui.animations.append_empty(delay).chain();
-                sim.defer_play_sound(tile_sound()).chain();
-                sim.launch_particle(p, to, TILE, SKYBLUE, 3., 0.1).chain();
-                sim.add_tiles(1).chain();
-              }
-            }
-          }
-          for p in fulfilled_quests.keys() {
-            sim.quests.remove(*p);
-          }
-        }
-
+        // progress on quests that care where the player is
+        sim.check_quests();
 
         // try to collect prize
         if let Some(&prize) = sim.prizes.get(target) {
-          sim.prizes.remove(target);
-          let to = sim.ui.layout[&HudItem::Hp].center();
-          sim.ui.animations.append_empty(0.).reserve(PLAYER_UNIT_ID);
-          sim.defer_play_sound(LEVEL_UP_SOUND).chain();
-          sim.launch_particle(target, to,
-            prize_img(prize), RED,
-            3., 0.02
-          ).chain();
-          sim.full_heal().chain();
+          sim.collect_prize(target, prize);
         }
       }
 
 
       if sim.player_dead() {
-        sim.player_defeat = true;
-        let dirvec: Vec2 = (sim.player_pos - target).into();
-        let mut velocity: Vec2 = Vec2::from(dirvec) * 3.;
-        velocity.x += (sim.rng.next_u32() % 1000) as f32 / 1000.;
-        velocity.y += (sim.rng.next_u32() % 1000) as f32 / 1000.;
-        velocity *= 2.;
-        sim.animate_unit_fling(
-          PLAYER_UNIT_ID,
-          sim.player_pos.into(),
-          velocity,
-          2.).reserve(PLAYER_UNIT_ID);
-        sim.defer_set_hud(|hud| hud.defeat = true).chain();
+        sim.defeat_player(target);
       }
       if !player_moved { sim.ui.compass_flash = 0.6; }
     }
@@ -1366,6 +2600,9 @@ async fn main() {
     //debug!("{:?}", sim.player_pos);
     let camera_offset: IVec = display.camera_focus - sim.player_pos;
     display.camera_focus = sim.player_pos + CAMERA_TETHER.clamp_pos(camera_offset);
+    if let Some(entry) = sim.ui.log_cursor.and_then(|i| sim.ui.hud.log.get(i)) {
+      display.camera_focus = sim.player_pos + torus_offset(BOARD_RECT, entry.at - sim.player_pos);
+    }
     unsafe {
       *sim.ui.camera_ref.get() = display.camera_focus;
     }
@@ -1377,38 +2614,55 @@ async fn main() {
         sim.add_monster_turns(1).chain();
         monsters_go = true;
         sim.update_player_dmap();
+        // the player has had their chance to dodge
+        for (_, aim) in sim.sealed.aims.iter_mut() {
+          aim.ready = true;
+        }
       }
-      let mut spawns = vec!();
-
       let mut acceleration = 1.0;
-      while monsters_go && sim.monster_turns > 0 {
-        sim.sealed.enemy_supply += sim.sealed.void_frontier.len() as i64;
-        spawns.clear();
-        sim.update_nearest_dmap();
+      while monsters_go && sim.monster_turns > 0 && !sim.player_defeat {
+        sim.tick_quests();
+        if sim.sealed.frozen_turns > 0 {
+          // frozen monsters sit this turn out
+          sim.sealed.frozen_turns -= 1;
+          sim.ui.animations.append_empty(BASE_ANIMATION_DURATION / acceleration).chain();
+          sim.add_monster_turns(-1).chain();
+          continue;
+        }
+        sim.prepare_enemy_behaviors();
         //do monster turn
-        for (&pos, &_nme) in sim.enemies.clone().iter() {
-          let maybe_pos = enemy_pathfind(&mut sim, pos);
+        for (&pos, &nme) in sim.enemies.clone().iter() {
+          if sim.player_defeat { break; }
+          if nme.t == EnemyType::GhostWitch {
+            sim.boss_turn(pos);
+            continue;
+          }
+          if nme.t == EnemyType::Sue && sim.ranged_attack(pos, acceleration) {
+            if sim.player_dead() {
+              sim.defeat_player(pos);
+            }
+            continue;
+          }
+          let maybe_pos = sim.enemy_pathfind(pos);
           if let Some(new_pos) = maybe_pos {
             sim.move_enemy(pos, new_pos, acceleration);
           }
           //debug!("a monster turn happened at {:?}", pos)
         }
+        sim.tick_boss_respawn();
         //spawn monsters maybe
-        for &p in sim.sealed.void_frontier.iter() {
-          if sim.enemies.contains_key(p) {
-            // don't spawn a monster if there's already a monster
-            continue;
-          }
-          if ((sim.rng.next_u64() % 5000) as i64 ) < sim.sealed.enemy_supply {
-            //spawn a monster in this tile
-            let random_enemy_type =
-              EnemyType::list()[(sim.rng.next_u32() % 3) as usize];
-            spawns.push((random_enemy_type,p));
-            //debug!("spawned a monster {:?} at {:?}", nme.t, p)
-          }
-        }
-        for(t,p) in &spawns {
-          sim.spawn_enemy(*t,*p);
+        let progress = sim.progress();
+        let spawns = sim.sealed.director.plan_spawns(
+          progress,
+          &sim.sealed.void_frontier,
+          &sim.enemies,
+          &sim.sealed.board,
+          sim.player_pos,
+          &mut sim.rng,
+        );
+        for (nme,p) in spawns {
+          sim.sealed.director.spend();
+          sim.place_enemy(nme,p);
         }
 
         sim.ui.animations.append_empty(BASE_ANIMATION_DURATION / acceleration).chain();
@@ -1421,13 +2675,40 @@ async fn main() {
 
     }
 
-    sim.tick_animations();
+    if player_moved {
+      sim.claim_quests();
+    }
+    sim.check_puzzle();
+
+    if let Some(puzzle) = sim.puzzle.as_ref().filter(|_| !recorded) {
+      // puzzles keep their own best results, apart from the profile and scores
+      if sim.player_victory {
+        new_best = bests.record(&puzzle.id, sim.puzzle_result());
+        bests.save();
+      }
+      recorded = sim.player_victory || sim.player_defeat;
+    }
+
+    if (sim.player_defeat || sim.player_victory) && !recorded {
+      let before = profile.clone();
+      profile.record(&sim.run_summary());
+      profile.save();
+      rank = scores.add(sim.score_record());
+      scores.save();
+      recorded = true;
+      unlocked = profile.unlocked_since(&before);
+      for c in unlocked.iter() {
+        let text = format!("Unlocked the {}", c.name());
+        sim.log_event(text, sim.player_pos, YELLOW).chain();
+      }
+    }
+
+    if sim.ui.show_intents && sim.ui.intents_dirty {
+      sim.update_intents();
+    }
 
+    sim.tick_animations();
 
-    let scale: f32 = f32::min(
-      screen_width() / display.dim.x as f32,
-      screen_height() / display.dim.y as f32,
-    );
 
     const DRAW_BOUNDS:IRect = IRect{ x: -9, y:-8, width: 18, height: 17};
     { // Redraw the display
@@ -1442,10 +2723,21 @@ async fn main() {
       //  draw_circle(v.x, v.y, 20., BLUE);
       //}
 
+      sim.update_fog();
+      let fog = sim.ui.fog_of_war;
+      let visible = |p: Position| !fog || sim.ui.fog_visible.contains(p);
+      let shown_tile = |p: Position| {
+        if visible(p) { sim.sealed.board[p] } else { sim.ui.remembered[p] }
+      };
+      // what stepping into each neighboring fight would cost
+      let previews: Vec<(Dir4, CombatPreview)> = Dir4::list().iter()
+        .filter_map(|&d| sim.preview_combat(d).map(|preview| (d, preview)))
+        .collect();
+
       // Draw tile backgrounds
       for offset in DRAW_BOUNDS.iter() {
-        let p = sim.player_pos + offset;
-        if sim.sealed.board[p] == Tile::default() { continue; }
+        let p = display.camera_focus + offset;
+        if shown_tile(p) == Tile::default() { continue; }
         let r = display.pos_rect(p.into());
         draw_rectangle(r.x, r.y, r.w, r.h, DARKBROWN);
         //display.draw_tile_1(r, tile, terrain);
@@ -1453,8 +2745,8 @@ async fn main() {
       // draw terrain
       for &terrain in Terrain::DRAW_ORDER {
         for offset in DRAW_BOUNDS.iter() {
-          let p = sim.player_pos + offset;
-          let mut tile = sim.sealed.board[p];
+          let p = display.camera_focus + offset;
+          let mut tile = shown_tile(p);
           if terrain == Terrain::None && sim.ui.hud.hidden_spaces.contains(p) {
             tile = Tile::default();
           }
@@ -1463,6 +2755,13 @@ async fn main() {
 
         }
       }
+      // dim remembered tiles that are out of sight
+      for offset in DRAW_BOUNDS.iter() {
+        let p = display.camera_focus + offset;
+        if visible(p) || shown_tile(p) == Tile::default() { continue; }
+        let r = display.pos_rect(p.into());
+        draw_rectangle(r.x, r.y, r.w, r.h, Color{a: 0.55, ..BLACK});
+      }
       // draw region hints
       for rid in &sim.sealed.open_regions {
         let font_size = 40;
@@ -1487,22 +2786,29 @@ async fn main() {
       }
       // draw terrain highlights
       for offset in DRAW_BOUNDS.iter() {
-        let p = sim.player_pos + offset;
+        let p = display.camera_focus + offset;
         let r = display.pos_rect(p.into());
         if sim.ui.hud.highlighted_spaces.contains(p) {
           display.draw_img(r, SKYBLUE, &BOX);
         }
       }
       for offset in DRAW_BOUNDS.iter() { // draw quests and prizes
-        let p = sim.player_pos + offset;
+        let p = display.camera_focus + offset;
+        if !visible(p) { continue; }
         let r = display.pos_rect(p.into());
         if sim.quests.contains_key(p) {
           let quest = sim.quests[p];
           draw_quest(&display, &r, &quest);
         }
-        if let Some(prize) = sim.prizes.get(p) {
-          let img = prize_img(*prize);
-          display.draw_img(r, RED, &img);
+        if let Some(&prize) = sim.prizes.get(p) {
+          let img = prize_img(prize);
+          display.draw_img(r, prize.color(), &img);
+        }
+        // spaces reach quests want the player on
+        for (&q, quest) in sim.quests.iter() {
+          if quest.mark(q).is_some_and(|m| equivalent(m, p)) {
+            display.draw_img(r, BEIGE, &FLAG);
+          }
         }
       }
 
@@ -1511,8 +2817,9 @@ async fn main() {
       }
       // tile placement hints
       for offset in DRAW_BOUNDS.iter() {
-        let p = sim.player_pos + offset;
+        let p = display.camera_focus + offset;
         if !sim.sealed.void_frontier.contains(p) { continue; }
+        if !visible(p) { continue; }
         {
           let compat = sim.tile_compatibility(p, sim.player_current_tile());
           if compat == 1 {
@@ -1544,12 +2851,75 @@ async fn main() {
       debug_draw = false;
 
       // draw enemies
-      for ragdoll in sim.ui.ragdolls.values() {
+      for (&id, ragdoll) in sim.ui.ragdolls.iter() {
+        if id != PLAYER_UNIT_ID && !visible(IVec::rounded(ragdoll.pos)) { continue; }
         display.draw_grid(
           ragdoll.pos,
           ragdoll.color,
           &ragdoll.img
         );
+        if ragdoll.pips > 0 && !ragdoll.dead {
+          let r = display.pos_rect(ragdoll.pos);
+          let size = r.w * 0.12;
+          for i in 0..ragdoll.pips {
+            let x = r.x + r.w * 0.5 + (i as f32 - 0.5 * ragdoll.pips as f32) * size * 1.5;
+            draw_rectangle(x, r.y + size * 0.5, size, size, RED);
+          }
+        }
+      }
+
+      // draw enemy intents once everything has settled
+      if sim.ui.show_intents && sim.ui.animations.is_empty() {
+        for intent in sim.ui.intents.iter() {
+          let from = display.camera_focus + torus_offset(BOARD_RECT, intent.from - display.camera_focus);
+          if !visible(from) { continue; }
+          let certain = intent.options.len() == 1;
+          for &option in intent.options.iter() {
+            let Ok(dir) = Dir4::try_from(torus_offset(BOARD_RECT, option - intent.from)) else {
+              continue;
+            };
+            let color = if certain {
+              ORANGE
+            } else if option == intent.to {
+              Color{a: 0.8, ..ORANGE}
+            } else {
+              Color{a: 0.35, ..ORANGE}
+            };
+            let r = display.pos_rect(Vec2::from(from) + 0.5 * Vec2::from(dir));
+            let s = 0.4;
+            let r = Rect {
+              x: r.x + 0.5 * (1. - s) * r.w,
+              y: r.y + 0.5 * (1. - s) * r.h,
+              w: s * r.w,
+              h: s * r.h,
+            };
+            display.draw_img(r, color, &arrow_img(dir));
+          }
+        }
+      }
+
+      // draw combat previews
+      for &(d, preview) in previews.iter() {
+        let target = sim.player_pos + d.into();
+        let r = display.pos_rect(Vec2::from(target));
+        let text = preview.text();
+        let font_size = 36;
+        let metrics = measure_text(&text, None, font_size, 1.);
+        let x = r.x + 0.5 * (r.w - metrics.width);
+        let y = r.y + r.h - 0.5 * metrics.height;
+        draw_rectangle(x - 6., y - metrics.offset_y - 4., metrics.width + 12., metrics.height + 8.,
+          Color{a: 0.6, ..BLACK});
+        draw_text(&text, x, y, font_size as f32, preview.color());
+      }
+
+      // draw announced shots
+      for (_, &(from, dir)) in sim.ui.hud.aims.iter() {
+        for p in shot_path(&sim.sealed.board, from, dir) {
+          let p = display.camera_focus + torus_offset(BOARD_RECT, p - display.camera_focus);
+          if !visible(p) { continue; }
+          let r = display.pos_rect(p.into());
+          display.draw_img(r, Color{a: 0.5, ..RED}, &arrow_img(dir));
+        }
       }
 
       // draw player path
@@ -1580,8 +2950,9 @@ async fn main() {
 
       // draw boss count
       for offset in DRAW_BOUNDS.iter() { // draw quests and prized
-        let p = sim.player_pos + offset;
-        if p != BOSS_LOCATION { continue; }
+        let p = display.camera_focus + offset;
+        if !equivalent(p, sim.ui.hud.boss_pos) { continue; }
+        if !visible(p) { continue; }
         let r = display.pos_rect(p.into());
         let text = format!("{}", sim.ui.hud.bosses);
         let font_size = 70;
@@ -1594,6 +2965,17 @@ async fn main() {
             font_size as f32, MONSTER_COLOR
             );
 
+          let phase = sim.ui.hud.boss_phase;
+          if phase != BossPhase::Dormant {
+            let font_size = 32;
+            let metrics = measure_text(phase.name(), None, font_size, 1.);
+            let leftover = r.w - metrics.width;
+            draw_text(phase.name(), r.x + 0.5 * leftover,
+              r.y + r.h + metrics.height,
+              font_size as f32, MONSTER_COLOR
+              );
+          }
+
         }
       }
 
@@ -1614,9 +2996,83 @@ async fn main() {
           sim.ui.layout.insert(HudItem::Bar, rect);
         }
 
+        { // Event log
+          let window = log_window(sim.ui.hud.log.len(), sim.ui.log_cursor);
+          let log_font_size = 36.;
+          let rect = Rect {
+            x: margin,
+            y: margin,
+            w: display.dim.x * 0.5,
+            h: LOG_LINE_HEIGHT * window.len() as f32,
+          };
+          if !window.is_empty() {
+            draw_rectangle(rect.x, rect.y, rect.w, rect.h, Color{a: 0.6, ..BLACK});
+          }
+          for (line, i) in window.enumerate() {
+            let entry = &sim.ui.hud.log[i];
+            let y = rect.y + LOG_LINE_HEIGHT * line as f32;
+            let selected = sim.ui.log_cursor == Some(i);
+            let marker = 0.4 * LOG_LINE_HEIGHT;
+            draw_rectangle(rect.x + margin, y + 0.3 * LOG_LINE_HEIGHT, marker, marker, entry.color);
+            let color = if selected { YELLOW } else { WHITE };
+            let x = rect.x + 2. * margin + marker;
+            draw_text(&entry.text, x, y + 0.75 * LOG_LINE_HEIGHT, log_font_size, color);
+          }
+          sim.ui.layout.insert(HudItem::Log, rect);
+        }
+
+        if let Some(puzzle) = &sim.puzzle { // puzzle goal
+          let text = puzzle.goal.describe();
+          let bar = sim.ui.layout[&HudItem::Bar];
+          let metrics = measure_text(&text, None, font_size, font_scale);
+          let x = 0.5 * (display.dim.x - metrics.width);
+          let y = bar.y - 2. * margin - metrics.height + metrics.offset_y;
+          draw_rectangle(x - margin, y - metrics.offset_y - margin,
+            metrics.width + 2. * margin, metrics.height + 2. * margin,
+            Color{a: 0.6, ..BLACK});
+          draw_text(&text, x, y, font_size as f32, YELLOW);
+        }
+
+        if sim.ui.hud.phase_banner > 0. { // boss phase announcement
+          let text = sim.ui.hud.boss_phase.announcement();
+          let metrics = measure_text(text, None, font_size, font_scale);
+          let x = 0.5 * (display.dim.x - metrics.width);
+          let y = 3. * margin + metrics.offset_y;
+          let a = sim.ui.hud.phase_banner.min(1.);
+          draw_rectangle(x - margin, y - metrics.offset_y - margin,
+            metrics.width + 2. * margin, metrics.height + 2. * margin,
+            Color{a: 0.6 * a, ..BLACK});
+          draw_text(text, x, y, font_size as f32, Color{a, ..MONSTER_COLOR});
+        }
+
+        if sim.showing_perks { // level up perk choice
+          let mut lines = vec![(format!("Level {}: choose a perk", sim.player_level + 1), YELLOW)];
+          for (i, perk) in sim.perk_choice.iter().enumerate() {
+            lines.push((format!("[{}] {}: {}", i + 1, perk.name(), perk.description()), WHITE));
+          }
+          lines.push(("[Z] to decide later".to_string(), GRAY));
+          let line_height = 70.;
+          let w = lines.iter()
+            .map(|(text, _)| measure_text(text, None, font_size, font_scale).width)
+            .fold(0., f32::max);
+          let h = line_height * lines.len() as f32;
+          let x = 0.5 * (display.dim.x - w);
+          let y = 0.5 * (display.dim.y - h);
+          draw_rectangle(x - 2. * margin, y - 2. * margin,
+            w + 4. * margin, h + 4. * margin,
+            Color{a: 0.8, ..BLACK});
+          for (i, (text, color)) in lines.iter().enumerate() {
+            draw_text(text, x, y + line_height * (i as f32 + 0.75), font_size as f32, *color);
+          }
+        }
+
         if sim.ui.hud.defeat {
             let bar = sim.ui.layout[&HudItem::Bar];
-            let display_text = format!("Defeated...");
+            let display_text = if sim.puzzle.is_some() {
+              "Puzzle failed...".to_string()
+            } else {
+              format!("Defeated... score {}", sim.score_record().score)
+            };
             let textdim: TextDimensions = measure_text(&display_text, None, font_size, font_scale);
             let leftover = bar.h - textdim.height;
             let x = (display.dim.x - textdim.width - margin)/2.;
@@ -1688,7 +3144,7 @@ async fn main() {
                 // no tiles left
                 if sim.player_tiles < 1 { continue; }
               }
-              if sim.in_combat() && !sim.is_road_dir(d) {
+              if sim.in_combat() && !sim.can_slip(d) {
                 // we can't step on void
                 if sim.sealed.board[target] == Tile::default() { continue; }
                 // we can't step on a free space
@@ -1700,6 +3156,16 @@ async fn main() {
                 &arrow_img(d),
               )
             }
+            for &(d, preview) in previews.iter() {
+              let text = format!("-{}", preview.damage);
+              let font_size = 32;
+              let metrics = measure_text(&text, None, font_size, 1.);
+              // board up is screen down
+              let v = Vec2::from(d);
+              let tip = rect.center() + 0.3 * rect.w * Vec2{x: v.x, y: -v.y};
+              draw_text(&text, tip.x - 0.5 * metrics.width, tip.y + 0.5 * metrics.offset_y,
+                font_size as f32, preview.color());
+            }
           }
 
           { // Current/Max HP and XP
@@ -1758,6 +3224,44 @@ async fn main() {
             sim.ui.layout.insert(HudItem::SpeedPenalty, icon_rect);
           }
 
+          if sim.ui.show_intents { // enemies next to the player after the next monster turn
+            let bar = sim.ui.layout[&HudItem::Bar];
+            let icon_rect = Rect{
+              x: bar.w * 0.62,
+              y: bar.y + margin,
+              w: sz.x,
+              h: sz.y,
+            };
+            let text = format!("{}", sim.ui.threats);
+            let textdim = measure_text(&text, None, font_size, font_scale);
+            let y = bar.y + 0.5 * (bar.h - textdim.height) + textdim.offset_y;
+            let x = icon_rect.x + icon_rect.w + margin;
+            let color = if sim.ui.threats > 0 { ORANGE } else { WHITE };
+            display.draw_img(icon_rect, MONSTER_COLOR, &enemy_img(EnemyType::Blinky, true));
+            draw_text(&text, x, y, font_size.into(), color);
+            sim.ui.layout.insert(HudItem::Threats, icon_rect);
+          }
+
+          { // held prizes, top right
+            let slot = sz.x + margin;
+            let rect = Rect {
+              x: display.dim.x - margin - slot * INVENTORY_SIZE as f32,
+              y: margin,
+              w: slot * INVENTORY_SIZE as f32,
+              h: sz.y,
+            };
+            for i in 0..INVENTORY_SIZE {
+              let r = Rect { x: rect.x + slot * i as f32, y: rect.y, w: sz.x, h: sz.y };
+              draw_rectangle(r.x, r.y, r.w, r.h, Color{a: 0.6, ..BLACK});
+              if let Some(&prize) = sim.inventory.get(i) {
+                display.draw_img(r, prize.color(), &prize_img(prize));
+                let key = format!("{}", i + 1);
+                draw_text(&key, r.x + 4., r.y + r.h - 4., 32., WHITE);
+              }
+            }
+            sim.ui.layout.insert(HudItem::Inventory, rect);
+          }
+
           if !tile_compat { // discard hint
             let bar = sim.ui.layout[&HudItem::Bar];
             let tile = sim.ui.layout[&HudItem::Tile];
@@ -1791,7 +3295,7 @@ async fn main() {
       }
 
       { // draw dmap2
-        // let dmap = &sim.sealed.nearest_enemy_dmap;
+        // let dmap = &sim.sealed.player_dmap;
         // for offset in (IRect{ x: -8, y:-8, width: 17, height: 17}).iter() {
         //  let p = sim.player_pos + offset;
         //  let dmapvalue = dmap[p];
@@ -1828,19 +3332,57 @@ async fn main() {
       }
     }
 
-    if sim.ui.hud.victory {
+    if let Some(puzzle) = sim.puzzle.as_ref().filter(|_| sim.ui.hud.victory) {
       clear_background(BLACK);
-      let score = sim.score_min_hp * sim.sealed.score_tiles_placed;
+      let result = sim.puzzle_result();
+      let best = bests.results.get(&puzzle.id).copied().unwrap_or(result);
       let mut y = 300.;
       let margin = 15.;
       let font_size = 64;
+      for (i, text) in [
+        format!("{} solved!", puzzle.name),
+        format!("Tiles Placed {}", result.tiles_placed),
+        format!("HP Left {}", result.hp),
+        format!("Monster Turns {}", result.turns),
+        if new_best {
+          "New best!".to_string()
+        } else {
+          format!("Best: {} tiles, {} HP", best.tiles_placed, best.hp)
+        },
+      ].iter().enumerate() {
+        let metrics = measure_text(text, None, font_size, 1.);
+        let x = 0.5 * (display.dim.x - metrics.width);
+        draw_text(text, x, y, font_size as f32, WHITE);
+        if i == 0 { y += margin * 2.; }
+        y += metrics.height + margin;
+      }
+    } else if sim.ui.hud.victory {
+      clear_background(BLACK);
+      let record = sim.score_record();
+      let mut y = 200.;
+      let margin = 15.;
+      let font_size = 64;
       let color = WHITE;
       let mut i = 0;
       for text in &[
         "Victory!",
-        &format!("Tiles Placed {} ", sim.sealed.score_tiles_placed),
-        &format!("Minimum HP {}", sim.score_min_hp),
-        &format!("Final Score {}", score),
+        &format!("Tiles Placed {} ", record.tiles_placed),
+        &format!("Minimum HP {}", record.min_hp),
+        &format!("Regions Closed {}", record.regions_closed),
+        &format!("Monster Turns {}", record.turns),
+        &format!("Final Score {}", record.score),
+        &match rank {
+          Some(r) => format!("High Score #{}", r + 1),
+          None => String::new(),
+        },
+        &format!("Class: {}", sim.class.name()),
+        &format!("Perks: {}", perk_summary(&sim.perks)),
+        &if !unlocked.is_empty() {
+          let names: Vec<&str> = unlocked.iter().map(|c| c.name()).collect();
+          format!("Unlocked: {}", names.join(", "))
+        } else {
+          String::new()
+        },
       ] {
         let metrics = measure_text(text, None, font_size, 1.);
         let x = 0.5 * (display.dim.x - metrics.width);
@@ -1853,28 +3395,15 @@ async fn main() {
       }
     }
 
-
-    { // Copy the display to the screen
-      set_default_camera();
-      clear_background(BLACK);
-
-      draw_texture_ex(
-        &display.texture,
-        (screen_width() - (scale * display.dim.x)) * 0.5,
-        (screen_height() - (scale * display.dim.y)) * 0.5,
-        WHITE,
-        DrawTextureParams {
-          dest_size: Some(vec2(
-            scale * display.dim.x as f32,
-            scale * display.dim.y as f32,
-          )),
-          flip_y: true,
-          ..Default::default()
-        },
-      );
+    if sim.ui.show_anim_debug {
+      draw_animation_debug(&display, &sim.ui.animations);
     }
 
+    // Copy the display to the screen
+    display.present();
+
     sim.ui.compass_flash -= get_frame_time();
+    sim.ui.hud.phase_banner -= get_frame_time();
     decay_sounds(get_frame_time());
     next_frame().await;
 
@@ -1882,143 +3411,3 @@ async fn main() {
 
 }
 
-fn select_candidate(mut candidates: Vec<Position>, sim: &mut SimulationState) -> Option<Position> {
-  // filter out invalid tiles
-  let mut valid: Vec<IVec> = Vec::new();
-  for c in candidates.drain(0..) {
-    if sim.sealed.board[c] != Tile::default() && !sim.enemies.contains_key(c) {
-      valid.push(c);
-    }
-  }
-  if valid.len() > 0 {
-    Some(valid[sim.rng.next_u32() as usize % valid.len()])
-  }
-  else {
-    None
-  }
-}
-
-
-fn enemy_pathfind(sim: &mut SimulationState, pos: Position) -> Option<Position> {
-  // add forest edges to valid set
-  let mut valid: Vec<Dir4> = forest_edges(&pos, &sim.sealed.board);
-  debug!("forest dirs {:?} for {:?}", valid, sim.enemies[pos]);
-  if valid.len() == 0 {
-    // no forest edges means anything is a candidate
-    valid = Dir4::list().into();
-  }
-  else {
-    // still have a chance to escape the forest
-    for d in Dir4::list().iter() {
-      if roll_chance(&mut sim.rng, FOREST_ESCAPE_CHANCE) {
-        valid.push(*d);
-      }
-    }
-  }
-  debug!("valid dirs {:?} for {:?}", valid, sim.enemies[pos]);
-
-  let mut candidates: Vec<IVec> = Vec::new();
-  for &d in &valid {
-    let target = pos + IVec::from(d);
-    // dont step on me
-    if equivalent(target, sim.player_pos) { continue; }
-    // no void
-    if sim.sealed.board[target] == Tile::default() { continue; }
-    // dont step on quest
-    if sim.quests.contains_key(target) { continue; }
-    // dont step on prize
-    if sim.prizes.contains_key(target) { continue; }
-    candidates.push(target);
-  }
-  if sim.sealed.board[pos] != Tile::default() {
-    candidates.push(pos);
-  }
-  match sim.enemies[pos].t {
-    EnemyType::Clyde => {}
-    EnemyType::Blinky => {
-      let mut min_score: i16 = i16::MAX;
-      for &c in &candidates {
-        min_score = min_score.min(sim.sealed.player_dmap[c]);
-      }
-      candidates = candidates.drain(..).filter(|c|{
-        min_score == sim.sealed.player_dmap[*c]
-      }).collect();
-    }
-    EnemyType::Pinky => {
-      let mut max_score: i16 = i16::MIN;
-      for &c in &candidates {
-        max_score = max_score.max(sim.sealed.nearest_enemy_dmap[c]);
-      }
-      candidates = candidates.drain(..).filter(|c|{
-        max_score == sim.sealed.nearest_enemy_dmap[*c]
-      }).collect();
-    }
-    EnemyType::GhostWitch => {
-      candidates.clear();
-      // boss does not move
-      candidates.push(pos);
-    }
-  }
-  select_candidate(candidates, sim)
-}
-
-pub fn forest_edges(pos: &Position, board: &Buffer2D<Tile>) -> Vec<Dir4> {
-  // right up left down (matching dir4.index)
-  let mut candidates: Vec<Dir4> = Vec::new();
-  let tile: Tile = board[*pos];
-  for ix in 0..4 {
-    let dir: Dir4 = Dir4::list()[ix];
-    let neighbor: Tile = board[*pos + dir.into()];
-    let edge1 = tile.contents[ix];
-    let edge2 = neighbor.contents[dir.opposite().index()];
-    if edge1 == Terrain::Forest && edge2 == Terrain::Forest {
-      candidates.push(dir);
-    }
-  }
-  candidates
-}
-
-pub fn eligible_for_quest(enemies: &WrapMap<Enemy>,
-                          quests: &WrapMap<Quest>,
-                          rng: &mut Rng) -> Option<Quest> {
-  // we are eligible for a quest if
-  // 1. there is an enemy type that doesn't have a quest yet
-  // 2. there is at least 1 of that enemy already on the map
-  let mut nme_counts: Map<EnemyType, u64> = Map::new();
-
-  // initialize
-  for nme_t in EnemyType::list()[0..3].iter() {
-    nme_counts.insert(*nme_t, 0);
-  }
-
-  // remove enemy types that already have a quest
-  for (_, quest) in quests.iter() {
-    nme_counts.remove(&quest.target);
-  }
-
-  // remove enemy types that don't have any spawned enemies
-  for (_, nme) in enemies.iter() {
-    if nme_counts.contains_key(&nme.t) {
-      let count = nme_counts[&nme.t];
-      nme_counts.insert(nme.t, count + 1);
-    }
-  }
-  nme_counts = nme_counts
-    .iter()
-    .filter(|(_, c)| **c > 0)
-    .map(|(k,v)| (*k, *v))
-    .collect();
-  debug!("nme_counts {:?}", nme_counts);
-
-  if nme_counts.len() > 0 {
-    let nme_types: Vec<&EnemyType> = nme_counts.keys().collect();
-    let selected_type = nme_types[rng.next_u32() as usize % nme_types.len()];
-    let mut quest = Quest::new();
-    let quota = nme_counts.get(selected_type).unwrap().max(&QUEST_MIN);
-    quest.target = *selected_type;
-    quest.quota = *quota;
-    Some(quest)
-  } else {
-    None
-  }
-}
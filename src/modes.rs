@@ -0,0 +1,49 @@
+use crate::*;
+
+// what kind of run is being played, picked on the title screen
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameMode {
+  // slay the GhostWitch to win
+  Standard,
+  // the GhostWitch keeps coming back, survive as long as you can
+  Endless,
+  // a fixed scenario with its own goal
+  Puzzle,
+}
+
+pub const MODES: [GameMode; 3] = [
+  GameMode::Standard,
+  GameMode::Endless,
+  GameMode::Puzzle,
+];
+
+impl GameMode {
+  pub fn name(self) -> &'static str {
+    match self {
+      GameMode::Standard => "Standard",
+      GameMode::Endless => "Endless",
+      GameMode::Puzzle => "Puzzle",
+    }
+  }
+
+  pub fn description(self) -> &'static str {
+    match self {
+      GameMode::Standard => "Slay the GhostWitch",
+      GameMode::Endless => "Survive as long as you can",
+      GameMode::Puzzle => "Solve a fixed scenario",
+    }
+  }
+
+  pub fn director(self) -> &'static DirectorConfig {
+    match self {
+      GameMode::Standard => &STANDARD_DIRECTOR,
+      GameMode::Endless => &ENDLESS_DIRECTOR,
+      GameMode::Puzzle => &PUZZLE_DIRECTOR,
+    }
+  }
+
+  // whether slaying the last GhostWitch ends the run
+  pub fn boss_ends_run(self) -> bool {
+    self == GameMode::Standard
+  }
+}
@@ -0,0 +1,104 @@
+use crate::*;
+
+// how many perks are offered at each level up
+pub const PERK_CHOICES: usize = 3;
+// forest escape chance (1/10 percent) is divided by this per Rootbind
+pub const ROOTBIND_DIVISOR: u64 = 2;
+
+// bonuses picked when leveling up
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Perk {
+  // +1 max HP
+  Vigor,
+  // discarding a tile doesn't cost one
+  Juggler,
+  // roads cross rivers without slowing the player down
+  Bridgewright,
+  // +1 tile for every closed region that pays out
+  Surveyor,
+  // monsters in a forest are less likely to leave it
+  Rootbind,
+}
+
+pub const PERK_POOL: [Perk; 5] = [
+  Perk::Vigor,
+  Perk::Juggler,
+  Perk::Bridgewright,
+  Perk::Surveyor,
+  Perk::Rootbind,
+];
+
+impl Perk {
+  pub fn name(self) -> &'static str {
+    match self {
+      Perk::Vigor => "Vigor",
+      Perk::Juggler => "Juggler",
+      Perk::Bridgewright => "Bridgewright",
+      Perk::Surveyor => "Surveyor",
+      Perk::Rootbind => "Rootbind",
+    }
+  }
+
+  pub fn description(self) -> &'static str {
+    match self {
+      Perk::Vigor => "+1 max HP",
+      Perk::Juggler => "Discarding is free",
+      Perk::Bridgewright => "Roads bridge rivers",
+      Perk::Surveyor => "+1 tile per closed region",
+      Perk::Rootbind => "Forest monsters escape less",
+    }
+  }
+
+  // perks that do nothing more when taken twice
+  pub fn unique(self) -> bool {
+    match self {
+      Perk::Vigor | Perk::Surveyor | Perk::Rootbind => false,
+      Perk::Juggler | Perk::Bridgewright => true,
+    }
+  }
+}
+
+// distinct perks to choose from, skipping unique perks already taken
+pub fn roll_perks(taken: &[Perk], rng: &mut Rng) -> Vec<Perk> {
+  let mut pool: Vec<Perk> = PERK_POOL.iter()
+    .copied()
+    .filter(|p| !(p.unique() && taken.contains(p)))
+    .collect();
+  let mut choices = vec![];
+  while choices.len() < PERK_CHOICES && !pool.is_empty() {
+    let i = rng.next_u32() as usize % pool.len();
+    choices.push(pool.swap_remove(i));
+  }
+  choices
+}
+
+// tiles for closing a region, given what it pays without perks
+// Surveyor only adds to regions that already pay out
+pub fn region_tile_reward(base: i64, taken: &[Perk]) -> i64 {
+  if base <= 0 { return 0; }
+  base + taken.iter().filter(|p| **p == Perk::Surveyor).count() as i64
+}
+
+pub fn forest_escape_chance(taken: &[Perk]) -> u64 {
+  let mut chance = FOREST_ESCAPE_CHANCE;
+  for _ in taken.iter().filter(|p| **p == Perk::Rootbind) {
+    chance /= ROOTBIND_DIVISOR;
+  }
+  chance
+}
+
+// "Vigor x2, Surveyor" in the order they were first taken
+pub fn perk_summary(taken: &[Perk]) -> String {
+  let mut seen: Vec<(Perk, usize)> = vec![];
+  for &perk in taken {
+    match seen.iter_mut().find(|(p, _)| *p == perk) {
+      Some((_, n)) => *n += 1,
+      None => seen.push((perk, 1)),
+    }
+  }
+  if seen.is_empty() { return "none".to_string(); }
+  let names: Vec<String> = seen.iter().map(|&(perk, n)| {
+    if n > 1 { format!("{} x{}", perk.name(), n) } else { perk.name().to_string() }
+  }).collect();
+  names.join(", ")
+}
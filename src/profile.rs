@@ -0,0 +1,130 @@
+use crate::*;
+
+// bump this when the saved format changes, and add a migration
+// from the previous version to MIGRATIONS
+pub const PROFILE_VERSION: u32 = 1;
+
+// migrations[i] upgrades the fields of a version i+1 profile to version i+2
+pub type Migration = fn(&mut Map<String, String>);
+pub const MIGRATIONS: &[Migration] = &[];
+
+// lifetime stats, kept between runs
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct Profile {
+  pub runs: i64,
+  pub wins: i64,
+  pub bosses_slain: i64,
+  pub largest_region: i64,
+}
+
+// how a run went, for the profile
+pub struct RunSummary {
+  pub won: bool,
+  pub bosses_slain: i64,
+  pub largest_region: i64,
+}
+
+// a milestone, and the class it unlocks
+pub struct Unlock {
+  pub class: PlayerClass,
+  pub requirement: &'static str,
+  pub reached: fn(&Profile) -> bool,
+}
+
+pub const UNLOCKS: &[Unlock] = &[
+  Unlock {
+    class: PlayerClass::Ranger,
+    requirement: "Play 3 runs",
+    reached: |p| p.runs >= 3,
+  },
+  Unlock {
+    class: PlayerClass::Builder,
+    requirement: "Close a region of 15",
+    reached: |p| p.largest_region >= 15,
+  },
+  Unlock {
+    class: PlayerClass::Ferryman,
+    requirement: "Slay 30 GhostWitches",
+    reached: |p| p.bosses_slain >= 30,
+  },
+];
+
+impl Profile {
+  pub fn record(&mut self, run: &RunSummary) {
+    self.runs += 1;
+    if run.won { self.wins += 1; }
+    self.bosses_slain += run.bosses_slain;
+    self.largest_region = self.largest_region.max(run.largest_region);
+  }
+
+  // None if the class is available, otherwise what it takes to unlock it
+  pub fn locked(&self, class: PlayerClass) -> Option<&'static str> {
+    UNLOCKS.iter()
+      .find(|u| u.class == class && !(u.reached)(self))
+      .map(|u| u.requirement)
+  }
+
+  pub fn unlocked_since(&self, before: &Profile) -> Vec<PlayerClass> {
+    UNLOCKS.iter()
+      .filter(|u| (u.reached)(self) && !(u.reached)(before))
+      .map(|u| u.class)
+      .collect()
+  }
+
+  // a version line, then one "key value" line per stat
+  pub fn serialize(&self) -> String {
+    format!("version {}\nruns {}\nwins {}\nbosses_slain {}\nlargest_region {}\n",
+      PROFILE_VERSION, self.runs, self.wins, self.bosses_slain, self.largest_region)
+  }
+
+  pub fn deserialize(text: &str) -> Result<Self, String> {
+    let mut fields: Map<String, String> = Map::new();
+    for line in text.lines() {
+      let line = line.trim();
+      if line.is_empty() { continue; }
+      let Some((key, value)) = line.split_once(' ') else {
+        return Err(format!("bad profile line {:?}", line));
+      };
+      fields.insert(key.to_string(), value.trim().to_string());
+    }
+    let version: u32 = fields.get("version")
+      .ok_or("profile has no version")?
+      .parse()
+      .map_err(|_| "bad profile version")?;
+    if version < 1 || version > PROFILE_VERSION {
+      return Err(format!("unknown profile version {}", version));
+    }
+    for migrate in &MIGRATIONS[(version - 1) as usize..] {
+      migrate(&mut fields);
+    }
+    let stat = |key: &str| -> Result<i64, String> {
+      match fields.get(key) {
+        Some(v) => v.parse().map_err(|_| format!("bad profile value for {}", key)),
+        None => Ok(0),
+      }
+    };
+    Ok(Profile {
+      runs: stat("runs")?,
+      wins: stat("wins")?,
+      bosses_slain: stat("bosses_slain")?,
+      largest_region: stat("largest_region")?,
+    })
+  }
+
+  // a missing or unreadable profile starts fresh, an unreadable one is backed up
+  pub fn load() -> Self {
+    let Some(text) = storage::read("profile") else { return Profile::default(); };
+    match Profile::deserialize(&text) {
+      Ok(profile) => profile,
+      Err(e) => {
+        warn!("{}, starting a new profile", e);
+        storage::back_up("profile");
+        Profile::default()
+      }
+    }
+  }
+
+  pub fn save(&self) {
+    storage::save("profile", &self.serialize());
+  }
+}
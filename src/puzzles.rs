@@ -0,0 +1,280 @@
+use crate::*;
+
+// bump this when the saved format changes
+pub const PUZZLE_BESTS_VERSION: u32 = 1;
+
+// what a puzzle asks of the player
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PuzzleGoal {
+  // close a region of this terrain with at least this many tiles
+  Close { terrain: Terrain, size: i64 },
+  // vanquish the GhostWitch with at least this much HP left
+  Boss { hp: i64 },
+  // slay every monster on the board
+  Clear,
+}
+
+impl PuzzleGoal {
+  pub fn describe(self) -> String {
+    match self {
+      PuzzleGoal::Close { terrain, size } => {
+        format!("Close a {:?} region of {} tiles", terrain, size)
+      }
+      PuzzleGoal::Boss { hp } => format!("Vanquish the GhostWitch with {} HP left", hp),
+      PuzzleGoal::Clear => "Slay every monster".to_string(),
+    }
+  }
+}
+
+// a fixed scenario, loaded from a data file in assets/puzzles
+#[derive(Clone, Debug)]
+pub struct Puzzle {
+  // the file name without its extension, used to keep best results
+  pub id: String,
+  pub name: String,
+  pub goal: PuzzleGoal,
+  // the usual starting HP if not given
+  pub hp: Option<i64>,
+  pub seed: u64,
+  pub player: Position,
+  pub board: Vec<(Position, Tile)>,
+  pub enemies: Vec<(Position, EnemyType)>,
+  // where the boss is and how many times it must be slain
+  pub boss: Option<(Position, usize)>,
+  // the tiles the player gets, in order
+  pub sequence: Vec<Tile>,
+}
+
+// one letter per terrain in a tile code
+fn terrain_code(c: char) -> Option<Terrain> {
+  match c {
+    '.' => Some(Terrain::None),
+    'g' => Some(Terrain::Grass),
+    't' => Some(Terrain::Town),
+    'w' => Some(Terrain::River),
+    'r' => Some(Terrain::Road),
+    'f' => Some(Terrain::Forest),
+    _ => None,
+  }
+}
+
+// five terrain letters: right up left down center
+pub fn parse_tile(code: &str) -> Result<Tile, String> {
+  let contents: Vec<Terrain> = code.chars()
+    .map(terrain_code)
+    .collect::<Option<_>>()
+    .ok_or(format!("bad tile {:?}", code))?;
+  let contents: [Terrain; 5] = contents.try_into()
+    .map_err(|_| format!("tile {:?} should have 5 terrains", code))?;
+  Ok(Tile { contents })
+}
+
+fn parse_terrain(name: &str) -> Result<Terrain, String> {
+  [Terrain::Grass, Terrain::Town, Terrain::River, Terrain::Road, Terrain::Forest]
+    .into_iter()
+    .find(|t| format!("{:?}", t).eq_ignore_ascii_case(name))
+    .ok_or(format!("unknown terrain {:?}", name))
+}
+
+fn parse_enemy(name: &str) -> Result<EnemyType, String> {
+  EnemyType::list()
+    .into_iter()
+    .find(|t| *t != EnemyType::GhostWitch && format!("{:?}", t).eq_ignore_ascii_case(name))
+    .ok_or(format!("unknown monster {:?}", name))
+}
+
+fn parse_number<T: std::str::FromStr>(word: Option<&str>, what: &str) -> Result<T, String> {
+  word.and_then(|w| w.parse().ok()).ok_or(format!("bad {}", what))
+}
+
+fn parse_position(words: &mut std::str::SplitWhitespace) -> Result<Position, String> {
+  let x = parse_number(words.next(), "x")?;
+  let y = parse_number(words.next(), "y")?;
+  Ok(IVec { x, y })
+}
+
+impl Puzzle {
+  // one "key values" line per entry, # starts a comment
+  //   name <text>               goal close <terrain> <size>
+  //   hp <n>                    goal boss <hp>
+  //   seed <n>                  goal clear
+  //   player <x> <y>            tile <x> <y> <code>
+  //   enemy <x> <y> <type>      boss <x> <y> <count>
+  //   next <code> <code> ...
+  pub fn parse(id: &str, text: &str) -> Result<Self, String> {
+    let mut puzzle = Puzzle {
+      id: id.to_string(),
+      name: id.to_string(),
+      goal: PuzzleGoal::Clear,
+      hp: None,
+      seed: 0,
+      player: IVec::ZERO,
+      board: vec![],
+      enemies: vec![],
+      boss: None,
+      sequence: vec![],
+    };
+    let mut has_goal = false;
+    for (i, line) in text.lines().enumerate() {
+      let line = line.split('#').next().unwrap().trim();
+      if line.is_empty() { continue; }
+      let at_line = |e: String| format!("{} line {}: {}", id, i + 1, e);
+      let mut words = line.split_whitespace();
+      let key = words.next().unwrap();
+      match key {
+        "name" => puzzle.name = line["name".len()..].trim().to_string(),
+        "hp" => puzzle.hp = Some(parse_number(words.next(), "hp").map_err(at_line)?),
+        "seed" => puzzle.seed = parse_number(words.next(), "seed").map_err(at_line)?,
+        "player" => puzzle.player = parse_position(&mut words).map_err(at_line)?,
+        "goal" => {
+          has_goal = true;
+          puzzle.goal = match words.next() {
+            Some("close") => {
+              let terrain = parse_terrain(words.next().unwrap_or("")).map_err(at_line)?;
+              let size = parse_number(words.next(), "size").map_err(at_line)?;
+              PuzzleGoal::Close { terrain, size }
+            }
+            Some("boss") => PuzzleGoal::Boss {
+              hp: parse_number(words.next(), "hp").map_err(at_line)?,
+            },
+            Some("clear") => PuzzleGoal::Clear,
+            other => return Err(at_line(format!("unknown goal {:?}", other))),
+          };
+        }
+        "tile" => {
+          let at = parse_position(&mut words).map_err(at_line)?;
+          let tile = parse_tile(words.next().unwrap_or("")).map_err(at_line)?;
+          puzzle.board.push((at, tile));
+        }
+        "enemy" => {
+          let at = parse_position(&mut words).map_err(at_line)?;
+          let t = parse_enemy(words.next().unwrap_or("")).map_err(at_line)?;
+          puzzle.enemies.push((at, t));
+        }
+        "boss" => {
+          let at = parse_position(&mut words).map_err(at_line)?;
+          let count = parse_number(words.next(), "boss count").map_err(at_line)?;
+          puzzle.boss = Some((at, count));
+        }
+        "next" => {
+          for code in words {
+            puzzle.sequence.push(parse_tile(code).map_err(at_line)?);
+          }
+        }
+        _ => return Err(at_line(format!("unknown key {:?}", key))),
+      }
+    }
+    if !has_goal { return Err(format!("{} has no goal", id)); }
+    if !puzzle.board.iter().any(|&(p, _)| equivalent(p, puzzle.player)) {
+      return Err(format!("{} doesn't start the player on a tile", id));
+    }
+    Ok(puzzle)
+  }
+}
+
+// every puzzle bundled in assets/puzzles, in file name order
+// a puzzle that doesn't parse is skipped with a warning
+pub fn load_puzzles() -> Vec<Puzzle> {
+  let Some(dir) = ASSETS.get_dir("puzzles") else { return vec![]; };
+  let mut files: Vec<_> = dir.files().collect();
+  files.sort_by_key(|f| f.path());
+  files.iter().filter_map(|file| {
+    let id = file.path().file_stem()?.to_str()?;
+    let text = file.contents_utf8()?;
+    match Puzzle::parse(id, text) {
+      Ok(puzzle) => Some(puzzle),
+      Err(e) => {
+        warn!("{}", e);
+        None
+      }
+    }
+  }).collect()
+}
+
+// how well a puzzle was solved
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PuzzleResult {
+  pub tiles_placed: i64,
+  pub hp: i64,
+  pub turns: i64,
+}
+
+impl PuzzleResult {
+  // fewer tiles is better, then more HP left, then fewer monster turns
+  pub fn beats(&self, other: &PuzzleResult) -> bool {
+    (self.tiles_placed, -self.hp, self.turns) < (other.tiles_placed, -other.hp, other.turns)
+  }
+}
+
+// the best result for each solved puzzle, kept between runs
+#[derive(Clone, Default, Debug)]
+pub struct PuzzleBests {
+  pub results: Map<String, PuzzleResult>,
+}
+
+impl PuzzleBests {
+  // returns whether this is a new best
+  pub fn record(&mut self, id: &str, result: PuzzleResult) -> bool {
+    if let Some(best) = self.results.get(id) {
+      if !result.beats(best) { return false; }
+    }
+    self.results.insert(id.to_string(), result);
+    true
+  }
+
+  pub fn serialize(&self) -> String {
+    let mut text = format!("version {}\n", PUZZLE_BESTS_VERSION);
+    for (id, r) in self.results.iter() {
+      text.push_str(&format!("{} tiles={} hp={} turns={}\n", id, r.tiles_placed, r.hp, r.turns));
+    }
+    text
+  }
+
+  pub fn deserialize(text: &str) -> Result<Self, String> {
+    let mut lines = text.lines().filter(|l| !l.trim().is_empty());
+    let header = lines.next().ok_or("empty puzzle results")?;
+    let version: u32 = header.strip_prefix("version ")
+      .and_then(|v| v.trim().parse().ok())
+      .ok_or("puzzle results have no version")?;
+    if version != PUZZLE_BESTS_VERSION {
+      return Err(format!("unknown puzzle results version {}", version));
+    }
+    let mut bests = PuzzleBests::default();
+    for line in lines {
+      let mut words = line.split_whitespace();
+      let id = words.next().unwrap();
+      let mut fields: Map<&str, i64> = Map::new();
+      for pair in words {
+        let Some((key, value)) = pair.split_once('=') else {
+          return Err(format!("bad puzzle result field {:?}", pair));
+        };
+        let value = value.parse().map_err(|_| format!("bad puzzle result value for {}", key))?;
+        fields.insert(key, value);
+      }
+      let field = |key: &str| fields.get(key).copied().ok_or(format!("puzzle result has no {}", key));
+      bests.results.insert(id.to_string(), PuzzleResult {
+        tiles_placed: field("tiles")?,
+        hp: field("hp")?,
+        turns: field("turns")?,
+      });
+    }
+    Ok(bests)
+  }
+
+  // missing or unreadable results start empty, unreadable ones are backed up
+  pub fn load() -> Self {
+    let Some(text) = storage::read("puzzles") else { return PuzzleBests::default(); };
+    match PuzzleBests::deserialize(&text) {
+      Ok(bests) => bests,
+      Err(e) => {
+        warn!("{}, starting new puzzle results", e);
+        storage::back_up("puzzles");
+        PuzzleBests::default()
+      }
+    }
+  }
+
+  pub fn save(&self) {
+    storage::save("puzzles", &self.serialize());
+  }
+}
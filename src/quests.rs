@@ -0,0 +1,251 @@
+use crate::*;
+
+// slay quests ask for at least this many kills
+pub const QUEST_MIN: u64 = 3;
+// tiles for the easiest quest of each kind, harder ones pay more
+pub const QUEST_REWARD: i64 = 5;
+// a ready scroll is claimed from this close (max norm), so on it or next to it
+pub const CLAIM_RANGE: i16 = 1;
+
+// what a quest asks of the player
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Objective {
+  // slay this many more of an enemy type
+  Slay { target: EnemyType, quota: u64 },
+  // close a region of this terrain with at least this many tiles
+  Close { terrain: Terrain, size: i64 },
+  // join the quest tile to a road network at least this many tiles big
+  Connect { length: i64 },
+  // step on the space at this offset from the quest tile
+  // before the monster turns run out
+  Reach { offset: IVec, turns: i64 },
+  // go this many monster turns in a row without losing health
+  Survive { turns: i64, left: i64 },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Quest {
+  pub objective: Objective,
+  // tiles granted on completion
+  pub reward: i64,
+  // the objective is met and the scroll is ready to claim
+  pub done: bool,
+  pub id: u64,
+}
+
+impl Quest {
+  pub fn new(objective: Objective) -> Self {
+    let reward = QUEST_REWARD + match objective {
+      Objective::Slay { quota, .. } => quota.saturating_sub(QUEST_MIN) as i64,
+      Objective::Close { size, .. } => (size - 6) / 2,
+      Objective::Connect { length } => length - 4,
+      Objective::Reach { offset, turns } => {
+        2 * torus_max_norm(BOARD_RECT, offset) as i64 - turns
+      }
+      Objective::Survive { turns, .. } => (turns - 6) / 2,
+    };
+    let id = crate::next_unit_id();
+    Quest { objective, reward: reward.max(1), done: false, id }
+  }
+
+  pub fn credit_kill(&mut self, t: EnemyType) {
+    if let Objective::Slay { target, quota } = &mut self.objective {
+      if *target == t && *quota > 0 {
+        *quota -= 1;
+        self.done = *quota == 0;
+      }
+    }
+  }
+
+  pub fn credit_region(&mut self, t: Terrain, n: i64) {
+    if let Objective::Close { terrain, size } = self.objective {
+      if terrain == t && n >= size { self.done = true; }
+    }
+  }
+
+  pub fn credit_damage(&mut self) {
+    if let Objective::Survive { turns, left } = &mut self.objective {
+      if !self.done { *left = *turns; }
+    }
+  }
+
+  // a monster turn has passed, false if the quest ran out of time
+  pub fn tick(&mut self) -> bool {
+    if self.done { return true; }
+    match &mut self.objective {
+      Objective::Reach { turns, .. } => {
+        *turns -= 1;
+        *turns > 0
+      }
+      Objective::Survive { left, .. } => {
+        *left -= 1;
+        self.done = *left <= 0;
+        true
+      }
+      _ => true,
+    }
+  }
+
+  // progress that depends on the board, for a quest on the tile at `at`
+  pub fn check(&mut self, at: Position, board: &Buffer2D<Tile>, player_pos: Position) {
+    match self.objective {
+      Objective::Reach { offset, .. } if equivalent(player_pos, at + offset) => self.done = true,
+      Objective::Connect { length } if road_network(board, at).len() as i64 >= length => {
+        self.done = true;
+      }
+      _ => {}
+    }
+  }
+
+  // the space a reach quest wants the player on
+  pub fn mark(&self, at: Position) -> Option<Position> {
+    match self.objective {
+      Objective::Reach { offset, .. } if !self.done => Some(at + offset),
+      _ => None,
+    }
+  }
+
+  // the number shown on the scroll
+  pub fn counter(&self) -> i64 {
+    if self.done { return 0; }
+    match self.objective {
+      Objective::Slay { quota, .. } => quota as i64,
+      Objective::Close { size, .. } => size,
+      Objective::Connect { length } => length,
+      Objective::Reach { turns, .. } => turns,
+      Objective::Survive { left, .. } => left,
+    }
+  }
+
+  pub fn describe(&self) -> String {
+    match self.objective {
+      Objective::Slay { target, .. } => format!("{:?} quest", target),
+      Objective::Close { terrain, size } => format!("{:?} {} quest", terrain, size),
+      Objective::Connect { length } => format!("Road {} quest", length),
+      Objective::Reach { .. } => "Reach quest".to_string(),
+      Objective::Survive { turns, .. } => format!("Survive {} quest", turns),
+    }
+  }
+}
+
+// picks a quest for the next tile, if any kind of quest fits
+// only one quest of each kind (and slay target or terrain) at a time
+pub fn roll_quest(
+  enemies: &WrapMap<Enemy>,
+  quests: &WrapMap<Quest>,
+  tile: Tile,
+  rng: &mut Rng,
+) -> Option<Quest> {
+  let mut options: Vec<Objective> = vec![];
+  // ready scrolls don't block a new quest of their kind
+  let active: Vec<Objective> = quests.iter()
+    .filter(|(_, q)| !q.done)
+    .map(|(_, q)| q.objective)
+    .collect();
+  let roll = |rng: &mut Rng, n: u32| (rng.next_u32() % n) as i64;
+
+  // slay an enemy type that is on the map
+  let mut nme_counts: Map<EnemyType, u64> = Map::new();
+  for (_, nme) in enemies.iter() {
+    if nme.t == EnemyType::GhostWitch { continue; }
+    *nme_counts.entry(nme.t).or_insert(0) += 1;
+  }
+  for o in active.iter() {
+    if let Objective::Slay { target, .. } = o { nme_counts.remove(target); }
+  }
+  debug!("nme_counts {:?}", nme_counts);
+  if !nme_counts.is_empty() {
+    let nme_types: Vec<&EnemyType> = nme_counts.keys().collect();
+    let target = *nme_types[rng.next_u32() as usize % nme_types.len()];
+    let quota = nme_counts[&target].max(QUEST_MIN);
+    options.push(Objective::Slay { target, quota });
+  }
+
+  // close a region
+  let terrains = [Terrain::Grass, Terrain::Forest, Terrain::Town];
+  let terrain = terrains[rng.next_u32() as usize % terrains.len()];
+  if !active.iter().any(|o| matches!(o, Objective::Close { terrain: t, .. } if *t == terrain)) {
+    options.push(Objective::Close { terrain, size: 6 + roll(rng, 7) });
+  }
+
+  // connect this tile to a road network
+  let has_kind = |f: fn(&Objective) -> bool| active.iter().any(f);
+  if tile.count(Terrain::Road) > 0 && !has_kind(|o| matches!(o, Objective::Connect { .. })) {
+    options.push(Objective::Connect { length: 4 + roll(rng, 5) });
+  }
+
+  // reach a space a few steps from this tile
+  if !has_kind(|o| matches!(o, Objective::Reach { .. })) {
+    let d = Dir4::list()[rng.next_u32() as usize % 4];
+    let distance = 4 + roll(rng, 4) as i16;
+    let sideways = roll(rng, 3) as i16 - 1;
+    let offset = distance * IVec::from(d) + sideways * IVec::from(d.rotate4(1));
+    let turns = distance as i64 + 2 + roll(rng, 3);
+    options.push(Objective::Reach { offset, turns });
+  }
+
+  // stay unhurt for a while
+  if !has_kind(|o| matches!(o, Objective::Survive { .. })) {
+    let turns = 6 + roll(rng, 7);
+    options.push(Objective::Survive { turns, left: turns });
+  }
+
+  if options.is_empty() { return None; }
+  let objective = options[rng.next_u32() as usize % options.len()];
+  Some(Quest::new(objective))
+}
+
+// the ready scrolls the player can claim this turn
+// claims wait for the monster turn, and take the HP the player has left
+// after it, so nothing is claimed on a turn that killed the player
+pub fn claimable_quests(
+  quests: &WrapMap<Quest>,
+  player_pos: Position,
+  hp_after_monsters: i64,
+) -> Vec<Position> {
+  if hp_after_monsters < 1 { return vec![]; }
+  quests.iter()
+    .filter(|(&p, q)| q.done && torus_max_norm(BOARD_RECT, p - player_pos) <= CLAIM_RANGE)
+    .map(|(&p, _)| p)
+    .collect()
+}
+
+pub fn draw_quest(display: &Display, r: &Rect, quest: &Quest) {
+  let color = if quest.done { GOLD } else { BEIGE };
+  display.draw_img(*r, color, &SCROLL);
+  let mut er: Rect = *r;
+  er.scale(0.5, 0.5);
+  er = er.offset(Vec2{ x: r.w/4., y: r.h /4. });
+  match quest.objective {
+    Objective::Slay { target, .. } => {
+      display.draw_img(er, BLACK, &enemy_img(target, false));
+    }
+    Objective::Close { terrain, .. } => {
+      let mut sq = er;
+      sq.scale(0.6, 0.6);
+      sq = sq.offset(Vec2{ x: 0.2 * er.w, y: 0.2 * er.h });
+      draw_rectangle(sq.x, sq.y, sq.w, sq.h, terrain.color());
+      draw_rectangle_lines(sq.x, sq.y, sq.w, sq.h, 3., BLACK);
+    }
+    Objective::Connect { .. } => {
+      let h = 0.2 * er.h;
+      let y = er.y + 0.5 * (er.h - h);
+      draw_rectangle(er.x, y, er.w, h, Terrain::Road.color());
+      draw_rectangle_lines(er.x, y, er.w, h, 3., BLACK);
+    }
+    Objective::Reach { .. } => {
+      display.draw_img(er, BLACK, &FLAG);
+    }
+    Objective::Survive { .. } => {
+      display.draw_img(er, BLACK, &HEART);
+    }
+  }
+  let quest_text = if quest.done { "!".to_string() } else { format!("{}", quest.counter()) };
+  let font_size = 50;
+  let font_scale = 1.;
+  let textdim: TextDimensions = measure_text(&quest_text, None, font_size, font_scale);
+  let margin = 0.;
+  let text_x = er.x + textdim.width * 0.8 - margin;
+  let text_y = er.y + textdim.height * 0.2;
+  draw_text(&quest_text, text_x, text_y, font_size.into(), BLACK);
+}
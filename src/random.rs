@@ -11,9 +11,12 @@ pub fn from_global_rng() -> Rng {
 }
 
 pub fn from_current_time() -> Rng {
+  Rng::new(seed_from_current_time(), 0)
+}
+
+pub fn seed_from_current_time() -> u64 {
   let now:f64 = macroquad::miniquad::date::now();
-  let state: u64 = unsafe { std::mem::transmute(now) };
-  Rng::new(state, 0)
+  unsafe { std::mem::transmute(now) }
 }
 
 // Pcg32 copied from rand_pcg
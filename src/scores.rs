@@ -0,0 +1,265 @@
+use crate::*;
+
+// bump this when the saved format changes
+pub const SCORES_VERSION: u32 = 1;
+// only the best runs of each mode are kept
+pub const MAX_SCORES: usize = 50;
+// what a defeat scores for each region closed, every so many monster turns
+// and each boss slain, on top of a point per tile placed
+pub const DEFEAT_REGION_SCORE: i64 = 5;
+pub const DEFEAT_TURNS_PER_POINT: i64 = 10;
+pub const DEFEAT_BOSS_SCORE: i64 = 10;
+
+// one finished run
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ScoreRecord {
+  pub score: i64,
+  pub won: bool,
+  pub seed: u64,
+  // YYYY-MM-DD
+  pub date: String,
+  pub class: String,
+  pub mode: String,
+  pub turns: i64,
+  pub tiles_placed: i64,
+  pub min_hp: i64,
+  pub regions_closed: i64,
+  pub bosses_slain: i64,
+}
+
+impl ScoreRecord {
+  // a win scores min hp times tiles placed
+  // a defeat scores how far the run got, with a bonus for each boss slain
+  pub fn compute_score(
+    won: bool,
+    tiles_placed: i64,
+    min_hp: i64,
+    regions_closed: i64,
+    turns: i64,
+    bosses_slain: i64,
+  ) -> i64 {
+    if won {
+      min_hp.max(1) * tiles_placed
+    } else {
+      tiles_placed
+        + DEFEAT_REGION_SCORE * regions_closed
+        + turns / DEFEAT_TURNS_PER_POINT
+        + DEFEAT_BOSS_SCORE * bosses_slain
+    }
+  }
+
+  // endless runs can't be won, they score the monster turns survived
+  pub fn survival_score(turns: i64) -> i64 {
+    turns
+  }
+
+  fn serialize(&self) -> String {
+    format!("score={} won={} seed={} date={} class={} mode={} turns={} tiles={} min_hp={} regions={} bosses={}",
+      self.score, self.won, self.seed, self.date, self.class, self.mode, self.turns,
+      self.tiles_placed, self.min_hp, self.regions_closed, self.bosses_slain)
+  }
+
+  fn deserialize(line: &str) -> Result<Self, String> {
+    let mut fields: Map<&str, &str> = Map::new();
+    for pair in line.split_whitespace() {
+      let Some((key, value)) = pair.split_once('=') else {
+        return Err(format!("bad score field {:?}", pair));
+      };
+      fields.insert(key, value);
+    }
+    let field = |key: &str| fields.get(key).copied().ok_or(format!("score has no {}", key));
+    let number = |key: &str| -> Result<i64, String> {
+      field(key)?.parse().map_err(|_| format!("bad score value for {}", key))
+    };
+    Ok(ScoreRecord {
+      score: number("score")?,
+      won: field("won")? == "true",
+      seed: field("seed")?.parse().map_err(|_| "bad score seed")?,
+      date: field("date")?.to_string(),
+      class: field("class")?.to_string(),
+      // tables from before endless mode only had standard runs
+      mode: field("mode").unwrap_or("Standard").to_string(),
+      turns: number("turns")?,
+      tiles_placed: number("tiles")?,
+      min_hp: number("min_hp")?,
+      regions_closed: number("regions")?,
+      bosses_slain: number("bosses")?,
+    })
+  }
+}
+
+// the columns of the high score table, each one sortable
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ScoreField {
+  Score,
+  Date,
+  Class,
+  Mode,
+  Turns,
+  Tiles,
+  MinHp,
+  Regions,
+  Bosses,
+  Seed,
+}
+
+impl ScoreField {
+  pub const ALL: [ScoreField; 10] = [
+    ScoreField::Score,
+    ScoreField::Date,
+    ScoreField::Class,
+    ScoreField::Mode,
+    ScoreField::Turns,
+    ScoreField::Tiles,
+    ScoreField::MinHp,
+    ScoreField::Regions,
+    ScoreField::Bosses,
+    ScoreField::Seed,
+  ];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      ScoreField::Score => "Score",
+      ScoreField::Date => "Date",
+      ScoreField::Class => "Class",
+      ScoreField::Mode => "Mode",
+      ScoreField::Turns => "Turns",
+      ScoreField::Tiles => "Tiles",
+      ScoreField::MinHp => "Min HP",
+      ScoreField::Regions => "Regions",
+      ScoreField::Bosses => "Bosses",
+      ScoreField::Seed => "Seed",
+    }
+  }
+
+  pub fn show(self, r: &ScoreRecord) -> String {
+    match self {
+      ScoreField::Score => format!("{}{}", r.score, if r.won { "" } else { "*" }),
+      ScoreField::Date => r.date.clone(),
+      ScoreField::Class => r.class.clone(),
+      ScoreField::Mode => r.mode.clone(),
+      ScoreField::Turns => format!("{}", r.turns),
+      ScoreField::Tiles => format!("{}", r.tiles_placed),
+      ScoreField::MinHp => format!("{}", r.min_hp),
+      ScoreField::Regions => format!("{}", r.regions_closed),
+      ScoreField::Bosses => format!("{}", r.bosses_slain),
+      // the full seed doesn't fit
+      ScoreField::Seed => format!("{:08x}", r.seed as u32),
+    }
+  }
+
+  // best first for numbers, newest first for dates, a to z for classes and modes
+  pub fn compare(self, a: &ScoreRecord, b: &ScoreRecord) -> std::cmp::Ordering {
+    match self {
+      // survival scores don't compare with standard ones, so rank within mode
+      ScoreField::Score => mode_order(&a.mode).cmp(&mode_order(&b.mode))
+        .then(b.score.cmp(&a.score)),
+      ScoreField::Date => b.date.cmp(&a.date),
+      ScoreField::Class => a.class.cmp(&b.class),
+      ScoreField::Mode => a.mode.cmp(&b.mode),
+      ScoreField::Turns => b.turns.cmp(&a.turns),
+      ScoreField::Tiles => b.tiles_placed.cmp(&a.tiles_placed),
+      ScoreField::MinHp => b.min_hp.cmp(&a.min_hp),
+      ScoreField::Regions => b.regions_closed.cmp(&a.regions_closed),
+      ScoreField::Bosses => b.bosses_slain.cmp(&a.bosses_slain),
+      ScoreField::Seed => a.seed.cmp(&b.seed),
+    }
+  }
+}
+
+// modes in the order the table lists them, unknown ones last
+fn mode_order(mode: &str) -> usize {
+  MODES.iter().position(|m| m.name() == mode).unwrap_or(MODES.len())
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct ScoreTable {
+  // grouped by mode, best score first
+  pub records: Vec<ScoreRecord>,
+}
+
+impl ScoreTable {
+  // returns the rank of the new record among runs of its mode,
+  // or None if it didn't make the table
+  pub fn add(&mut self, record: ScoreRecord) -> Option<usize> {
+    let at = self.records.iter()
+      .position(|r| ScoreField::Score.compare(&record, r).is_lt())
+      .unwrap_or(self.records.len());
+    let rank = self.records[..at].iter().filter(|r| r.mode == record.mode).count();
+    if rank >= MAX_SCORES { return None; }
+    let mode = record.mode.clone();
+    self.records.insert(at, record);
+    let mut kept = 0;
+    self.records.retain(|r| {
+      if r.mode != mode { return true; }
+      kept += 1;
+      kept <= MAX_SCORES
+    });
+    Some(rank)
+  }
+
+  pub fn sorted_by(&self, field: ScoreField) -> Vec<&ScoreRecord> {
+    let mut sorted: Vec<&ScoreRecord> = self.records.iter().collect();
+    // stable, so ties stay in score order
+    sorted.sort_by(|a, b| field.compare(a, b));
+    sorted
+  }
+
+  pub fn serialize(&self) -> String {
+    let mut text = format!("version {}\n", SCORES_VERSION);
+    for r in &self.records {
+      text.push_str(&r.serialize());
+      text.push('\n');
+    }
+    text
+  }
+
+  pub fn deserialize(text: &str) -> Result<Self, String> {
+    let mut lines = text.lines().filter(|l| !l.trim().is_empty());
+    let header = lines.next().ok_or("empty score table")?;
+    let version: u32 = header.strip_prefix("version ")
+      .and_then(|v| v.trim().parse().ok())
+      .ok_or("score table has no version")?;
+    if version != SCORES_VERSION {
+      return Err(format!("unknown score table version {}", version));
+    }
+    let mut table = ScoreTable::default();
+    for line in lines {
+      table.records.push(ScoreRecord::deserialize(line)?);
+    }
+    table.records.sort_by(|a, b| ScoreField::Score.compare(a, b));
+    Ok(table)
+  }
+
+  // a missing or unreadable table starts empty, an unreadable one is backed up
+  pub fn load() -> Self {
+    let Some(text) = storage::read("scores") else { return ScoreTable::default(); };
+    match ScoreTable::deserialize(&text) {
+      Ok(table) => table,
+      Err(e) => {
+        warn!("{}, starting a new score table", e);
+        storage::back_up("scores");
+        ScoreTable::default()
+      }
+    }
+  }
+
+  pub fn save(&self) {
+    storage::save("scores", &self.serialize());
+  }
+}
+
+// YYYY-MM-DD for a time in seconds since the unix epoch
+pub fn date_string(seconds: f64) -> String {
+  // days to civil date, from Howard Hinnant's date algorithms
+  let z = (seconds / 86400.).floor() as i64 + 719468;
+  let era = z.div_euclid(146097);
+  let doe = z - era * 146097;
+  let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = doy - (153 * mp + 2) / 5 + 1;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 };
+  let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+  format!("{:04}-{:02}-{:02}", year, month, day)
+}
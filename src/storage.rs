@@ -0,0 +1,106 @@
+// small named text files that outlive a run
+// files in the working directory on native, local storage on the web
+
+#[cfg(not(target_arch = "wasm32"))]
+mod imp {
+  use crate::*;
+
+  // <name>.txt, or set the uppercased name (e.g. PROFILE=<path>) to move it
+  fn path(name: &str) -> String {
+    std::env::var(name.to_uppercase()).unwrap_or(format!("{}.txt", name))
+  }
+
+  pub fn read(name: &str) -> Option<String> {
+    std::fs::read_to_string(path(name)).ok()
+  }
+
+  pub fn write(name: &str, text: &str) {
+    if let Err(e) = std::fs::write(path(name), text) {
+      warn!("failed to save {}: {}", name, e);
+    }
+  }
+
+  // moves <path> to <path>.bak, or .bak2, .bak3... if that's taken
+  pub fn back_up(name: &str) -> Result<String, String> {
+    let from = path(name);
+    let mut to = format!("{}.bak", from);
+    let mut n = 1;
+    while std::path::Path::new(&to).exists() {
+      n += 1;
+      to = format!("{}.bak{}", from, n);
+    }
+    std::fs::rename(&from, &to).map_err(|e| e.to_string())?;
+    Ok(to)
+  }
+}
+
+// see www/storage.js
+#[cfg(target_arch = "wasm32")]
+mod imp {
+  extern "C" {
+    fn storage_len(name: *const u8, name_len: u32) -> u32;
+    fn storage_read(name: *const u8, name_len: u32, ptr: *mut u8, len: u32);
+    fn storage_write(name: *const u8, name_len: u32, ptr: *const u8, len: u32);
+  }
+
+  pub fn read(name: &str) -> Option<String> {
+    let n = name.len() as u32;
+    let len = unsafe { storage_len(name.as_ptr(), n) };
+    if len == 0 { return None; }
+    let mut buf = vec![0u8; len as usize];
+    unsafe { storage_read(name.as_ptr(), n, buf.as_mut_ptr(), len); }
+    String::from_utf8(buf).ok()
+  }
+
+  pub fn write(name: &str, text: &str) {
+    unsafe {
+      storage_write(name.as_ptr(), name.len() as u32, text.as_ptr(), text.len() as u32);
+    }
+  }
+
+  // copies <name> to <name>.bak, or .bak2, .bak3... if that's taken
+  pub fn back_up(name: &str) -> Result<String, String> {
+    let text = read(name).ok_or(format!("nothing saved as {}", name))?;
+    let mut to = format!("{}.bak", name);
+    let mut n = 1;
+    while read(&to).is_some() {
+      n += 1;
+      to = format!("{}.bak{}", name, n);
+    }
+    write(&to, &text);
+    Ok(to)
+  }
+
+  // checked against the plugin version on startup
+  #[no_mangle]
+  pub extern "C" fn storage_crate_version() -> u32 {
+    1
+  }
+}
+
+use crate::*;
+pub use imp::{read, write};
+
+// names whose unreadable contents couldn't be backed up, never saved over
+static KEEP: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+
+// for saves that couldn't be read back: sets them aside so the next save
+// starts fresh without losing them, or keeps them from being saved over
+pub fn back_up(name: &str) {
+  match imp::back_up(name) {
+    Ok(to) => warn!("kept the old {} as {}", name, to),
+    Err(e) => {
+      warn!("failed to back up {}: {}, it won't be saved over", name, e);
+      KEEP.lock().unwrap().push(name.to_string());
+    }
+  }
+}
+
+// like write, unless name couldn't be backed up
+pub fn save(name: &str, text: &str) {
+  if KEEP.lock().unwrap().iter().any(|n| n == name) {
+    warn!("not saving {} over a file that couldn't be read", name);
+    return;
+  }
+  write(name, text);
+}
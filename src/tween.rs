@@ -0,0 +1,209 @@
+use crate::*;
+use crate::footguns::Ref;
+use std::f32::consts::PI;
+
+// game feel, tuned here rather than at each animation
+pub const UNIT_MOTION_EASE: Ease = Ease::LINEAR;
+pub const UNIT_FLING_EASE: Ease = Ease::LINEAR;
+pub const SHOT_EASE: Ease = Ease::LINEAR;
+pub const TILE_LAUNCH_DURATION: Seconds = 0.15;
+pub const TILE_LAUNCH_EASE: Ease = Ease::LINEAR;
+pub const TILE_ROTATE_DURATION: Seconds = 0.5;
+pub const TILE_ROTATE_EASE: Ease = Ease::LINEAR;
+pub const FADE_EASE: Ease = Ease::LINEAR;
+
+// the shape of an easing curve, as it eases in
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Curve {
+  Linear,
+  Quad,
+  Cubic,
+  // pulls back a little before setting off
+  Back,
+  Elastic,
+  Bounce,
+}
+
+impl Curve {
+  // maps 0..1 onto 0..1, starting slow
+  pub fn ease_in(self, t: f32) -> f32 {
+    match self {
+      Curve::Linear => t,
+      Curve::Quad => t * t,
+      Curve::Cubic => t * t * t,
+      Curve::Back => {
+        let c1 = 1.70158;
+        let c3 = c1 + 1.;
+        c3 * t * t * t - c1 * t * t
+      }
+      Curve::Elastic => {
+        if t <= 0. || t >= 1. { return t; }
+        let c4 = 2. * PI / 3.;
+        -f32::powf(2., 10. * t - 10.) * f32::sin((10. * t - 10.75) * c4)
+      }
+      Curve::Bounce => 1. - bounce_out(1. - t),
+    }
+  }
+}
+
+fn bounce_out(t: f32) -> f32 {
+  let n1 = 7.5625;
+  let d1 = 2.75;
+  if t < 1. / d1 {
+    n1 * t * t
+  } else if t < 2. / d1 {
+    let t = t - 1.5 / d1;
+    n1 * t * t + 0.75
+  } else if t < 2.5 / d1 {
+    let t = t - 2.25 / d1;
+    n1 * t * t + 0.9375
+  } else {
+    let t = t - 2.625 / d1;
+    n1 * t * t + 0.984375
+  }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Ease {
+  In(Curve),
+  Out(Curve),
+  InOut(Curve),
+}
+
+impl Ease {
+  pub const LINEAR: Ease = Ease::In(Curve::Linear);
+
+  // always 0 at the start and 1 at the end, though it may overshoot between
+  pub fn apply(self, t: f32) -> f32 {
+    let t = t.clamp(0., 1.);
+    match self {
+      Ease::In(c) => c.ease_in(t),
+      Ease::Out(c) => 1. - c.ease_in(1. - t),
+      Ease::InOut(c) => {
+        if t < 0.5 {
+          c.ease_in(2. * t) / 2.
+        } else {
+          1. - c.ease_in(2. - 2. * t) / 2.
+        }
+      }
+    }
+  }
+}
+
+// values a tween can move between
+pub trait Lerp: Copy {
+  fn lerp(from: Self, to: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+  fn lerp(from: Self, to: Self, t: f32) -> Self {
+    from + (to - from) * t
+  }
+}
+
+impl Lerp for Vec2 {
+  fn lerp(from: Self, to: Self, t: f32) -> Self {
+    from + (to - from) * t
+  }
+}
+
+impl Lerp for Color {
+  fn lerp(from: Self, to: Self, t: f32) -> Self {
+    Color {
+      r: <f32 as Lerp>::lerp(from.r, to.r, t),
+      g: <f32 as Lerp>::lerp(from.g, to.g, t),
+      b: <f32 as Lerp>::lerp(from.b, to.b, t),
+      a: <f32 as Lerp>::lerp(from.a, to.a, t),
+    }
+  }
+}
+
+// something that changes over a fixed duration
+// run it with the time since it started; every step, including the last,
+// is applied exactly once, however coarse the time steps are
+pub struct Tween {
+  pub duration: Seconds,
+  run: Box<dyn FnMut(Seconds) -> bool>,
+}
+
+impl Tween {
+  // f is called with the eased progress, ending with 1
+  pub fn new(duration: Seconds, ease: Ease, mut f: impl FnMut(f32) + 'static) -> Self {
+    let mut done = false;
+    Tween {
+      duration,
+      run: Box::new(move |elapsed| {
+        if done { return false; }
+        let t = if duration > 0. { f64::clamp(elapsed / duration, 0., 1.) as f32 } else { 1. };
+        f(ease.apply(t));
+        done = t >= 1.;
+        !done
+      }),
+    }
+  }
+
+  // happens once, taking no time
+  pub fn call(mut f: impl FnMut() + 'static) -> Self {
+    Tween::new(0., Ease::LINEAR, move |_| f())
+  }
+
+  pub fn wait(duration: Seconds) -> Self {
+    Tween::new(duration, Ease::LINEAR, |_| {})
+  }
+
+  // one after another
+  pub fn sequence(mut tweens: Vec<Tween>) -> Self {
+    Tween {
+      duration: tweens.iter().map(|t| t.duration).sum(),
+      run: Box::new(move |elapsed| {
+        let mut start = 0.;
+        for tween in tweens.iter_mut() {
+          if tween.run(elapsed - start) { return true; }
+          start += tween.duration;
+        }
+        false
+      }),
+    }
+  }
+
+  // all at once, lasting as long as the longest
+  pub fn parallel(mut tweens: Vec<Tween>) -> Self {
+    Tween {
+      duration: tweens.iter().map(|t| t.duration).fold(0., f64::max),
+      run: Box::new(move |elapsed| {
+        let mut running = false;
+        for tween in tweens.iter_mut() {
+          running |= tween.run(elapsed);
+        }
+        running
+      }),
+    }
+  }
+
+  pub fn then(self, next: Tween) -> Self {
+    Tween::sequence(vec![self, next])
+  }
+
+  pub fn with(self, other: Tween) -> Self {
+    Tween::parallel(vec![self, other])
+  }
+
+  // returns whether the tween is still running
+  pub fn run(&mut self, elapsed: Seconds) -> bool {
+    (self.run)(elapsed)
+  }
+}
+
+// moves one field of a shared value from one value to another
+pub fn tween<T: 'static, V: Lerp + 'static>(
+  target: Ref<T>,
+  field: fn(&mut T) -> &mut V,
+  from: V,
+  to: V,
+  duration: Seconds,
+  ease: Ease,
+) -> Tween {
+  Tween::new(duration, ease, move |t| unsafe {
+    *field(target.get()) = V::lerp(from, to, t);
+  })
+}
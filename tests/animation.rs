@@ -0,0 +1,160 @@
+use rl2025::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+fn pos(x: i16, y: i16) -> Position {
+  IVec { x, y }
+}
+
+// a queue and a log of which animations have started
+fn queue() -> (AnimationQueue, Rc<RefCell<Vec<&'static str>>>) {
+  (AnimationQueue::starting_at(0.), Rc::new(RefCell::new(vec![])))
+}
+
+fn logged<'a>(
+  q: &'a mut AnimationQueue,
+  log: &Rc<RefCell<Vec<&'static str>>>,
+  name: &'static str,
+  duration: Seconds,
+) -> &'a mut Animation {
+  let log = log.clone();
+  let mut started = false;
+  q.append(move |t| {
+    if !started { log.borrow_mut().push(name); started = true; }
+    t.elapsed < duration
+  })
+}
+
+#[test]
+fn test_reserve_blocks_reserve() {
+  let a = AnimLock::reserves(pos(3, 4));
+  assert!(a.overlaps(&AnimLock::reserves(pos(3, 4))));
+  assert!(!a.overlaps(&AnimLock::reserves(pos(4, 4))));
+}
+
+#[test]
+fn test_require_blocks_only_reserve() {
+  let a = AnimLock::requires(pos(3, 4));
+  assert!(!a.overlaps(&AnimLock::requires(pos(3, 4))));
+  assert!(a.overlaps(&AnimLock::reserves(pos(3, 4))));
+  assert!(AnimLock::reserves(pos(3, 4)).overlaps(&a));
+}
+
+#[test]
+fn test_ids_and_positions_are_separate() {
+  let a = AnimLock::reserves(5 as UnitId);
+  assert!(a.overlaps(&AnimLock::reserves(5 as UnitId)));
+  assert!(!a.overlaps(&AnimLock::reserves(pos(5, 0))));
+}
+
+#[test]
+fn test_no_false_conflicts() {
+  // these used to share a bit
+  assert_eq!(pack_id(1 as UnitId), pack_id(65 as UnitId));
+  assert!(!AnimLock::reserves(1 as UnitId).overlaps(&AnimLock::reserves(65 as UnitId)));
+  assert_eq!(pack_location(pos(0, 0)), pack_location(pos(8, 0)));
+  assert!(!AnimLock::reserves(pos(0, 0)).overlaps(&AnimLock::reserves(pos(8, 0))));
+}
+
+#[test]
+fn test_wrapped_positions_conflict() {
+  let a = AnimLock::reserves(pos(-1, 0));
+  assert!(a.overlaps(&AnimLock::reserves(pos(BOARD_RECT.width - 1, 0))));
+}
+
+#[test]
+fn test_full_locks() {
+  let some = AnimLock::empty().reserve(pos(1, 1)).reserve(7 as UnitId);
+  assert!(AnimLock::full().overlaps(&some));
+  assert!(AnimLock::full().overlaps(&AnimLock::requires(7 as UnitId)));
+  assert!(!AnimLock::full().overlaps(&AnimLock::empty()));
+  assert!(AnimLock::full_positions().overlaps(&AnimLock::requires(pos(1, 1))));
+  assert!(!AnimLock::full_positions().overlaps(&AnimLock::reserves(7 as UnitId)));
+}
+
+#[test]
+fn test_queue_waits_for_reserved_space() {
+  let (mut q, log) = queue();
+  logged(&mut q, &log, "a", 1.).reserve(pos(2, 2));
+  logged(&mut q, &log, "b", 1.).reserve([pos(2, 2), pos(2, 3)]);
+  logged(&mut q, &log, "c", 1.).reserve(pos(9, 9));
+  q.advance(0.5);
+  assert_eq!(*log.borrow(), vec!["a", "c"]);
+  q.advance(0.6);
+  assert_eq!(*log.borrow(), vec!["a", "c", "b"]);
+}
+
+#[test]
+fn test_queue_shares_required_space() {
+  let (mut q, log) = queue();
+  logged(&mut q, &log, "a", 1.).require(pos(2, 2));
+  logged(&mut q, &log, "b", 1.).require(pos(2, 2));
+  q.advance(0.1);
+  assert_eq!(*log.borrow(), vec!["a", "b"]);
+}
+
+#[test]
+fn test_queue_chain_waits() {
+  let (mut q, log) = queue();
+  logged(&mut q, &log, "a", 1.).reserve(pos(2, 2));
+  logged(&mut q, &log, "b", 1.).reserve(pos(5, 5)).chain();
+  q.advance(0.5);
+  assert_eq!(*log.borrow(), vec!["a"]);
+  q.advance(0.6);
+  assert_eq!(*log.borrow(), vec!["a", "b"]);
+}
+
+#[test]
+fn test_finish_all_runs_in_order() {
+  let (mut q, log) = queue();
+  logged(&mut q, &log, "a", 1.).reserve(pos(2, 2));
+  logged(&mut q, &log, "b", 1.).reserve(pos(2, 2));
+  logged(&mut q, &log, "c", 1.).chain();
+  q.sync();
+  logged(&mut q, &log, "d", 1.);
+  q.finish_all();
+  assert_eq!(q.len(), 0);
+  assert_eq!(*log.borrow(), vec!["a", "b", "c", "d"]);
+}
+
+#[test]
+fn test_finish_all_catches_up_the_hud() {
+  // stands in for the hud, updated the way defer_set_hud does
+  let hud = rl2025::footguns::Ref::new(0);
+  let (mut q, log) = queue();
+  logged(&mut q, &log, "walk", 2.).reserve(pos(1, 1));
+  let h = hud.clone();
+  q.append(move |_| unsafe { *h.get() += 5; false }).chain();
+  logged(&mut q, &log, "hit", 1.).require(pos(1, 1));
+  let h = hud.clone();
+  q.append(move |_| unsafe { *h.get() += 1; false }).chain();
+  q.advance(0.5);
+  assert_eq!(*hud.peek(), 0);
+  q.finish_all();
+  assert!(q.is_empty());
+  assert_eq!(*hud.peek(), 6);
+  assert_eq!(*log.borrow(), vec!["walk", "hit"]);
+}
+
+#[test]
+fn test_inspect_finds_blockers() {
+  let (mut q, log) = queue();
+  logged(&mut q, &log, "a", 1.).reserve(pos(2, 2)).label("a");
+  logged(&mut q, &log, "b", 1.).reserve(pos(9, 9)).label("b");
+  logged(&mut q, &log, "c", 1.).require(pos(9, 9)).label("c");
+  logged(&mut q, &log, "d", 1.).reserve(pos(5, 5)).chain();
+  q.advance(0.5);
+  let infos = q.inspect();
+  assert_eq!(infos.len(), 4);
+  assert_eq!(infos[0].label, Some("a"));
+  assert_eq!(infos[0].state, AnimationState::Active);
+  assert_eq!(infos[0].elapsed, 0.5);
+  assert_eq!(infos[1].blocked_by, None);
+  assert_eq!(infos[2].state, AnimationState::Waiting);
+  assert_eq!(infos[2].blocked_by, Some(1));
+  assert_eq!(infos[3].label, None);
+  assert_eq!(infos[3].state, AnimationState::Chain);
+  assert_eq!(infos[3].blocked_by, Some(2));
+  assert_eq!(infos[1].lock.reserve.describe(), "(9,9)");
+  assert_eq!(AnimLock::full().require.describe(), "all positions, all units");
+}
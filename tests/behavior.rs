@@ -0,0 +1,123 @@
+use rl2025::*;
+
+fn pos(x: i16, y: i16) -> Position {
+  IVec { x, y }
+}
+
+const GRASS: Tile = Tile { contents: [Terrain::Grass; 5] };
+
+// a 12x12 field of grass in the void, with the player in it
+struct World {
+  board: Buffer2D<Tile>,
+  enemies: WrapMap<Enemy>,
+  quests: WrapMap<Quest>,
+  prizes: WrapMap<Prize>,
+  player_pos: Position,
+  player_facing: Dir4,
+  player_dmap: DMap,
+  player_fov: WrapSet,
+  last_seen: Map<UnitId, Position>,
+}
+
+impl World {
+  fn new(player_pos: Position, player_facing: Dir4) -> Self {
+    let mut board = Buffer2D::new(Tile::default(), BOARD_RECT);
+    for x in 0..12 {
+      for y in 0..12 {
+        board[pos(x, y)] = GRASS;
+      }
+    }
+    let mut player_dmap = DijkstraMap::new(BOARD_RECT);
+    fill_dmap(&mut player_dmap, &board, vec![player_pos]);
+    // the player sees the whole field
+    let mut player_fov = WrapSet::new(BOARD_RECT);
+    for x in 0..12 {
+      for y in 0..12 {
+        player_fov.insert(pos(x, y));
+      }
+    }
+    World {
+      board,
+      enemies: WrapMap::new(BOARD_RECT),
+      quests: WrapMap::new(BOARD_RECT),
+      prizes: WrapMap::new(BOARD_RECT),
+      player_pos,
+      player_facing,
+      player_dmap,
+      player_fov,
+      last_seen: Map::new(),
+    }
+  }
+
+  fn view(&self) -> EnemyView<'_> {
+    EnemyView {
+      board: &self.board,
+      enemies: &self.enemies,
+      quests: &self.quests,
+      prizes: &self.prizes,
+      player_pos: self.player_pos,
+      player_facing: self.player_facing,
+      player_dmap: &self.player_dmap,
+      player_fov: &self.player_fov,
+      last_seen: &self.last_seen,
+      forest_escape_chance: 0,
+    }
+  }
+
+  // where the standard behavior for the enemy at p moves it
+  fn choose_move(&self, p: Position) -> Option<Position> {
+    let view = self.view();
+    let mut behaviors = Behaviors::standard();
+    behaviors.prepare(&view);
+    behaviors.choose_move(&view, p, &mut Rng::new(7, 0))
+  }
+}
+
+#[test]
+fn test_shy_moves_away_from_enemies() {
+  let mut world = World::new(pos(1, 1), Dir4::Right);
+  world.enemies.insert(pos(5, 5), Enemy::new(EnemyType::Pinky));
+  world.enemies.insert(pos(4, 5), Enemy::new(EnemyType::Blinky));
+  assert_eq!(world.choose_move(pos(5, 5)), Some(pos(6, 5)));
+}
+
+#[test]
+fn test_shy_ignores_other_pinkies() {
+  let mut world = World::new(pos(1, 1), Dir4::Right);
+  world.enemies.insert(pos(5, 5), Enemy::new(EnemyType::Pinky));
+  world.enemies.insert(pos(7, 5), Enemy::new(EnemyType::Blinky));
+  let alone = world.choose_move(pos(5, 5));
+  world.enemies.insert(pos(3, 6), Enemy::new(EnemyType::Pinky));
+  assert_eq!(world.choose_move(pos(5, 5)), alone);
+  assert!(alone.unwrap().x <= 5, "moved to {:?}", alone);
+}
+
+#[test]
+fn test_flank_heads_for_the_spot_ahead() {
+  let mut world = World::new(pos(5, 5), Dir4::Right);
+  world.enemies.insert(pos(7, 8), Enemy::new(EnemyType::Inky));
+  assert_eq!(Flank::target(&world.view()), pos(7, 5));
+  assert_eq!(world.choose_move(pos(7, 8)), Some(pos(7, 7)));
+}
+
+#[test]
+fn test_flank_reflects_away_from_blinky() {
+  let mut world = World::new(pos(5, 5), Dir4::Right);
+  world.enemies.insert(pos(7, 4), Enemy::new(EnemyType::Blinky));
+  assert_eq!(Flank::target(&world.view()), pos(7, 6));
+}
+
+#[test]
+fn test_snipe_lines_up_a_shot() {
+  let mut world = World::new(pos(5, 5), Dir4::Right);
+  world.enemies.insert(pos(8, 6), Enemy::new(EnemyType::Sue));
+  assert_eq!(world.choose_move(pos(8, 6)), Some(pos(8, 5)));
+}
+
+#[test]
+fn test_snipe_closes_in_without_a_line() {
+  let mut world = World::new(pos(2, 2), Dir4::Right);
+  world.enemies.insert(pos(9, 9), Enemy::new(EnemyType::Sue));
+  let to = world.choose_move(pos(9, 9)).unwrap();
+  assert_eq!(world.player_dmap[to], world.player_dmap[pos(9, 9)] - 1);
+}
@@ -0,0 +1,80 @@
+use rl2025::*;
+
+fn pos(x: i16, y: i16) -> Position {
+  IVec { x, y }
+}
+
+fn fallen(hp: i64) -> bool {
+  hp < 1
+}
+
+fn slain(blows: &[Blow]) -> Vec<EnemyType> {
+  blows.iter().filter_map(Blow::slain).collect()
+}
+
+// a line of enemies stretching right from the target
+fn line(types: &[EnemyType]) -> (WrapMap<Enemy>, Map<Position, u8>) {
+  let mut enemies = WrapMap::new(BOARD_RECT);
+  let mut crowd = Map::new();
+  for (i, &t) in types.iter().enumerate() {
+    enemies.insert(pos(i as i16, 0), Enemy::new(t));
+    crowd.insert(pos(i as i16, 0), i as u8);
+  }
+  (enemies, crowd)
+}
+
+#[test]
+fn test_types_differ() {
+  assert_eq!(EnemyType::Clyde.stats(), (1, 1));
+  assert!(EnemyType::Inky.stats().0 > 1);
+  assert!(EnemyType::Sue.stats().1 > 1);
+  assert_ne!(EnemyType::GhostWitch.stats(), EnemyType::Clyde.stats());
+}
+
+#[test]
+fn test_take_blow() {
+  let mut nme = Enemy::tough(EnemyType::Clyde);
+  assert_eq!(nme.pips(), 0);
+  assert!(!nme.take_blow());
+  assert_eq!(nme.pips(), 1);
+  assert!(nme.take_blow());
+}
+
+#[test]
+fn test_wounded_enemy_survives_an_exchange() {
+  let (mut enemies, crowd) = line(&[EnemyType::Inky]);
+  // the player falls after one exchange
+  let mut hp = 1;
+  let blows = fight_crowd(&mut enemies, &crowd, pos(0, 0), &mut hp, fallen);
+  assert_eq!(hp, 0);
+  let inky = *enemies.get(pos(0, 0)).unwrap();
+  assert_eq!(inky.hp, 1);
+  assert_eq!(inky.pips(), 1);
+  // the game draws the pips from the blow
+  assert_eq!(blows, vec![Blow::Hit(inky)]);
+}
+
+#[test]
+fn test_crowd_moves_up() {
+  let (mut enemies, crowd) = line(&[EnemyType::Clyde, EnemyType::Inky, EnemyType::Sue]);
+  let mut hp = 10;
+  let blows = fight_crowd(&mut enemies, &crowd, pos(0, 0), &mut hp, fallen);
+  assert_eq!(slain(&blows), vec![EnemyType::Clyde, EnemyType::Inky, EnemyType::Sue]);
+  // the whole line steps up after each fall
+  let Blow::Slain(_, moves) = &blows[0] else { panic!("{:?}", blows[0]); };
+  assert_eq!(moves, &vec![(pos(1, 0), pos(0, 0)), (pos(2, 0), pos(1, 0))]);
+  assert!(matches!(blows[1], Blow::Hit(Enemy { t: EnemyType::Inky, hp: 1, .. })));
+  // one blow for clyde, two for inky, sue hits for 2
+  assert_eq!(hp, 10 - 1 - 2 - 2);
+  assert_eq!(enemies.len(), 0);
+}
+
+#[test]
+fn test_fight_stops_when_player_falls() {
+  let (mut enemies, crowd) = line(&[EnemyType::Sue, EnemyType::Clyde]);
+  let mut hp = 2;
+  let blows = fight_crowd(&mut enemies, &crowd, pos(0, 0), &mut hp, fallen);
+  assert_eq!(slain(&blows), vec![EnemyType::Sue]);
+  // the clyde moved up, but never got a turn
+  assert_eq!(enemies.get(pos(0, 0)).map(|nme| nme.t), Some(EnemyType::Clyde));
+}
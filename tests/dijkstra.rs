@@ -0,0 +1,112 @@
+use rl2025::*;
+
+fn open(_from: Position, _to: Position) -> Option<i16> {
+  Some(1)
+}
+
+fn map_from(sources: &[Position]) -> DijkstraMap {
+  let mut dmap = DijkstraMap::new(BOARD_RECT);
+  dmap.fill(sources.iter().map(|&p| (p, 0)), open);
+  dmap
+}
+
+#[test]
+fn test_single_source() {
+  let dmap = map_from(&[IVec { x: 10, y: 10 }]);
+  assert_eq!(dmap[IVec { x: 10, y: 10 }], 0);
+  assert_eq!(dmap[IVec { x: 11, y: 10 }], 1);
+  assert_eq!(dmap[IVec { x: 13, y: 14 }], 7);
+}
+
+#[test]
+fn test_wraps_around() {
+  let dmap = map_from(&[IVec { x: 0, y: 0 }]);
+  assert_eq!(dmap[IVec { x: 49, y: 0 }], 1);
+  assert_eq!(dmap[IVec { x: 0, y: 48 }], 2);
+  assert_eq!(dmap[IVec { x: 25, y: 25 }], 50);
+  // positions off the board are read wrapped
+  assert_eq!(dmap[IVec { x: -1, y: 50 }], 1);
+}
+
+#[test]
+fn test_multiple_sources() {
+  let dmap = map_from(&[IVec { x: 0, y: 0 }, IVec { x: 10, y: 0 }]);
+  assert_eq!(dmap[IVec { x: 4, y: 0 }], 4);
+  assert_eq!(dmap[IVec { x: 7, y: 0 }], 3);
+  assert_eq!(dmap[IVec { x: 10, y: 2 }], 2);
+}
+
+#[test]
+fn test_sources_with_offsets() {
+  let mut dmap = DijkstraMap::new(BOARD_RECT);
+  dmap.fill([(IVec { x: 0, y: 0 }, 5), (IVec { x: 4, y: 0 }, 0)], open);
+  assert_eq!(dmap[IVec { x: 0, y: 0 }], 4);
+  assert_eq!(dmap[IVec { x: 2, y: 0 }], 2);
+}
+
+#[test]
+fn test_impassable() {
+  // a wall at x = 5, with a gap at y = 20
+  let wall = |_from: Position, to: Position| {
+    if to.x == 5 && to.y != 20 { None } else { Some(1) }
+  };
+  let mut dmap = DijkstraMap::new(IRect { x: 0, y: 0, width: 10, height: 40 });
+  dmap.fill([(IVec { x: 4, y: 10 }, 0)], wall);
+  assert!(!dmap.is_reachable(IVec { x: 5, y: 10 }));
+  // around through the gap, or around the other side of the torus
+  assert_eq!(dmap[IVec { x: 6, y: 10 }], 8);
+  assert_eq!(dmap[IVec { x: 6, y: 20 }], 12);
+}
+
+#[test]
+fn test_costs() {
+  // stepping along y = 0 is cheap
+  let road = |_from: Position, to: Position| {
+    if to.y == 0 { Some(1) } else { Some(3) }
+  };
+  let mut dmap = DijkstraMap::new(BOARD_RECT);
+  dmap.fill([(IVec { x: 0, y: 1 }, 0)], road);
+  assert_eq!(dmap[IVec { x: 0, y: 0 }], 1);
+  assert_eq!(dmap[IVec { x: 4, y: 0 }], 5);
+  // down to the road, along it, and back up
+  assert_eq!(dmap[IVec { x: 4, y: 1 }], 8);
+}
+
+#[test]
+fn test_diagonals() {
+  let mut dmap = DijkstraMap::new(BOARD_RECT).with_diagonals();
+  dmap.fill([(IVec { x: 10, y: 10 }, 0)], open);
+  assert_eq!(dmap[IVec { x: 13, y: 14 }], 4);
+}
+
+#[test]
+fn test_downhill() {
+  let dmap = map_from(&[IVec { x: 10, y: 10 }]);
+  assert_eq!(dmap.downhill(IVec { x: 12, y: 10 }), vec![IVec { x: 11, y: 10 }]);
+  assert_eq!(dmap.downhill(IVec { x: 11, y: 11 }).len(), 2);
+  assert!(dmap.downhill(IVec { x: 10, y: 10 }).is_empty());
+}
+
+#[test]
+fn test_flee() {
+  // a corridor 1 wide, with the source in the middle
+  let corridor = |_from: Position, to: Position| {
+    if to.y == 0 && to.x >= 0 && to.x < 30 { Some(1) } else { None }
+  };
+  let source = IVec { x: 10, y: 0 };
+  let mut dmap = DijkstraMap::new(BOARD_RECT);
+  dmap.fill([(source, 0)], corridor);
+  let flee = dmap.flee(2., corridor);
+
+  // everything reachable is still reachable, and nothing else is
+  assert!(flee.is_reachable(IVec { x: 0, y: 0 }));
+  assert!(!flee.is_reachable(IVec { x: 10, y: 1 }));
+  // the far end is the best place to be
+  assert!(flee[IVec { x: 29, y: 0 }] < flee[IVec { x: 0, y: 0 }]);
+  assert!(flee[IVec { x: 29, y: 0 }] < flee[source]);
+  // so next to the source, the way to go is towards the far end
+  assert_eq!(flee.downhill(IVec { x: 11, y: 0 }), vec![IVec { x: 12, y: 0 }]);
+  // with a high enough coefficient, the long way is worth running past the
+  // source for, even when the dead end is closer
+  assert_eq!(flee.downhill(IVec { x: 9, y: 0 }), vec![IVec { x: 10, y: 0 }]);
+}
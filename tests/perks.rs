@@ -0,0 +1,15 @@
+use rl2025::*;
+
+#[test]
+fn test_surveyor_needs_a_paying_region() {
+  // regions at or below the reward threshold pay nothing, perks or not
+  assert_eq!(region_tile_reward(0, &[Perk::Surveyor]), 0);
+  assert_eq!(region_tile_reward(0, &[Perk::Surveyor, Perk::Surveyor]), 0);
+}
+
+#[test]
+fn test_surveyor_stacks() {
+  assert_eq!(region_tile_reward(1, &[]), 1);
+  assert_eq!(region_tile_reward(1, &[Perk::Surveyor]), 2);
+  assert_eq!(region_tile_reward(1, &[Perk::Surveyor, Perk::Vigor, Perk::Surveyor]), 3);
+}
@@ -0,0 +1,10 @@
+use rl2025::*;
+
+#[test]
+fn test_no_reveal_without_fog() {
+  let mut rng = Rng::new(3, 0);
+  for _ in 0..500 {
+    assert_ne!(Prize::roll(&mut rng, false), Prize::Reveal);
+  }
+  assert!((0..500).any(|_| Prize::roll(&mut rng, true) == Prize::Reveal));
+}
@@ -0,0 +1,63 @@
+use rl2025::*;
+
+fn sample() -> Profile {
+  Profile { runs: 12, wins: 3, bosses_slain: 41, largest_region: 17 }
+}
+
+#[test]
+fn test_profile_round_trip() {
+  let text = sample().serialize();
+  assert!(text.starts_with(&format!("version {}\n", PROFILE_VERSION)));
+  assert_eq!(Profile::deserialize(&text), Ok(sample()));
+}
+
+#[test]
+fn test_v1_profile_loads() {
+  let text = "version 1\nruns 12\nwins 3\nbosses_slain 41\nlargest_region 17\n";
+  assert_eq!(Profile::deserialize(text), Ok(sample()));
+}
+
+#[test]
+fn test_missing_fields_default_to_zero() {
+  let profile = Profile::deserialize("version 1\nruns 4\n").unwrap();
+  assert_eq!(profile, Profile { runs: 4, ..Profile::default() });
+}
+
+#[test]
+fn test_bad_versions_are_rejected() {
+  let future = format!("version {}\nruns 4\n", PROFILE_VERSION + 1);
+  assert!(Profile::deserialize(&future).is_err());
+  assert!(Profile::deserialize("version 0\nruns 4\n").is_err());
+  assert!(Profile::deserialize("runs 4\n").is_err());
+  assert!(Profile::deserialize("version one\n").is_err());
+  assert!(Profile::deserialize("version 1\nruns many\n").is_err());
+}
+
+#[test]
+fn test_every_old_version_has_a_migration() {
+  assert_eq!(MIGRATIONS.len() as u32, PROFILE_VERSION - 1);
+}
+
+#[test]
+fn test_newer_profile_is_backed_up() {
+  let dir = std::env::temp_dir().join(format!("rl2025-profile-{}", std::process::id()));
+  std::fs::create_dir_all(&dir).unwrap();
+  let path = dir.join("profile.txt");
+  std::env::set_var("PROFILE", &path);
+  let future = format!("version {}\nruns 40\nwins 9\n", PROFILE_VERSION + 1);
+  std::fs::write(&path, &future).unwrap();
+
+  assert_eq!(Profile::load(), Profile::default());
+  // saving the fresh profile leaves the newer one alone
+  sample().save();
+  let backup = dir.join("profile.txt.bak");
+  assert_eq!(std::fs::read_to_string(&backup).unwrap(), future);
+  assert_eq!(Profile::load(), sample());
+
+  // a second bad profile doesn't replace the first backup
+  std::fs::write(&path, "version x\n").unwrap();
+  assert_eq!(Profile::load(), Profile::default());
+  assert_eq!(std::fs::read_to_string(&backup).unwrap(), future);
+  assert_eq!(std::fs::read_to_string(dir.join("profile.txt.bak2")).unwrap(), "version x\n");
+  std::fs::remove_dir_all(&dir).unwrap();
+}
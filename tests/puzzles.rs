@@ -0,0 +1,53 @@
+use rl2025::*;
+
+#[test]
+fn test_bundled_puzzles_load() {
+  let puzzles = load_puzzles();
+  assert!(puzzles.len() >= 3);
+  for puzzle in &puzzles {
+    assert!(!puzzle.sequence.is_empty(), "{} has no tiles", puzzle.id);
+  }
+}
+
+#[test]
+fn test_parse_puzzle() {
+  let text = "
+    # a comment
+    name Tiny
+    goal close river 3
+    hp 4
+    player 0 0
+    tile 0 0 wggg.
+    enemy 1 1 Blinky
+    boss 2 2 3
+    next wgwgw ggwg.
+  ";
+  let puzzle = Puzzle::parse("tiny", text).unwrap();
+  assert_eq!(puzzle.name, "Tiny");
+  assert_eq!(puzzle.goal, PuzzleGoal::Close { terrain: Terrain::River, size: 3 });
+  assert_eq!(puzzle.hp, Some(4));
+  assert_eq!(puzzle.enemies, vec![(IVec { x: 1, y: 1 }, EnemyType::Blinky)]);
+  assert_eq!(puzzle.boss, Some((IVec { x: 2, y: 2 }, 3)));
+  assert_eq!(puzzle.sequence.len(), 2);
+  assert_eq!(puzzle.board[0].1.contents[0], Terrain::River);
+}
+
+#[test]
+fn test_puzzle_errors() {
+  assert!(Puzzle::parse("none", "player 0 0\ntile 0 0 gggg.").is_err());
+  assert!(Puzzle::parse("void", "goal clear\nplayer 5 5\ntile 0 0 gggg.").is_err());
+  assert!(Puzzle::parse("bad", "goal clear\nplayer 0 0\ntile 0 0 gxgg.").is_err());
+  assert!(Puzzle::parse("short", "goal clear\nplayer 0 0\ntile 0 0 ggg").is_err());
+  assert!(Puzzle::parse("boss", "goal clear\nplayer 0 0\ntile 0 0 gggg.\nenemy 1 0 GhostWitch").is_err());
+}
+
+#[test]
+fn test_puzzle_bests() {
+  let mut bests = PuzzleBests::default();
+  let result = PuzzleResult { tiles_placed: 5, hp: 3, turns: 6 };
+  assert!(bests.record("a", result));
+  assert!(!bests.record("a", PuzzleResult { tiles_placed: 6, hp: 7, turns: 1 }));
+  assert!(bests.record("a", PuzzleResult { tiles_placed: 5, hp: 4, turns: 9 }));
+  let loaded = PuzzleBests::deserialize(&bests.serialize()).unwrap();
+  assert_eq!(loaded.results.get("a"), Some(&PuzzleResult { tiles_placed: 5, hp: 4, turns: 9 }));
+}
@@ -0,0 +1,114 @@
+use rl2025::*;
+
+fn pos(x: i16, y: i16) -> Position {
+  IVec { x, y }
+}
+
+fn ready_quest() -> Quest {
+  let mut quest = Quest::new(Objective::Slay { target: EnemyType::Blinky, quota: 1 });
+  quest.credit_kill(EnemyType::Blinky);
+  quest
+}
+
+fn one_quest(at: Position, quest: Quest) -> WrapMap<Quest> {
+  let mut quests = WrapMap::new(BOARD_RECT);
+  quests.insert(at, quest);
+  quests
+}
+
+#[test]
+fn test_kill_makes_scroll_ready() {
+  let mut quest = Quest::new(Objective::Slay { target: EnemyType::Blinky, quota: 2 });
+  quest.credit_kill(EnemyType::Clyde);
+  assert!(!quest.done);
+  quest.credit_kill(EnemyType::Blinky);
+  assert!(!quest.done);
+  quest.credit_kill(EnemyType::Blinky);
+  assert!(quest.done);
+}
+
+#[test]
+fn test_claim_standing_on_scroll() {
+  let quests = one_quest(pos(10, 10), ready_quest());
+  assert_eq!(claimable_quests(&quests, pos(10, 10), 5), vec![pos(10, 10)]);
+}
+
+#[test]
+fn test_claim_next_to_scroll() {
+  let quests = one_quest(pos(10, 10), ready_quest());
+  for offset in [pos(1, 0), pos(-1, 0), pos(0, 1), pos(0, -1), pos(1, 1), pos(-1, -1)] {
+    let player = pos(10, 10) + offset;
+    assert_eq!(claimable_quests(&quests, player, 5), vec![pos(10, 10)], "{:?}", offset);
+  }
+}
+
+#[test]
+fn test_no_claim_from_afar() {
+  let quests = one_quest(pos(10, 10), ready_quest());
+  assert!(claimable_quests(&quests, pos(12, 10), 5).is_empty());
+  assert!(claimable_quests(&quests, pos(10, 13), 5).is_empty());
+}
+
+#[test]
+fn test_claim_across_board_edge() {
+  let quests = one_quest(pos(0, 0), ready_quest());
+  let player = pos(BOARD_RECT.width - 1, 0);
+  assert_eq!(claimable_quests(&quests, player, 5), vec![pos(0, 0)]);
+}
+
+#[test]
+fn test_unfinished_quest_not_claimed() {
+  let quest = Quest::new(Objective::Slay { target: EnemyType::Blinky, quota: 3 });
+  let quests = one_quest(pos(10, 10), quest);
+  assert!(claimable_quests(&quests, pos(10, 10), 5).is_empty());
+}
+
+#[test]
+fn test_no_claim_on_lethal_turn() {
+  let quests = one_quest(pos(10, 10), ready_quest());
+  // the player steps onto the scroll with 2 HP,
+  // then a sue's shot lands during the monster turn
+  let hp_before = 2;
+  let hp_after = hp_before - Enemy::new(EnemyType::Sue).attack;
+  assert!(!claimable_quests(&quests, pos(10, 10), hp_before).is_empty());
+  assert!(claimable_quests(&quests, pos(10, 10), hp_after).is_empty());
+  assert!(claimable_quests(&quests, pos(11, 10), hp_after).is_empty());
+  assert!(claimable_quests(&quests, pos(10, 10), 0).is_empty());
+}
+
+#[test]
+fn test_claim_after_surviving_monsters() {
+  let quests = one_quest(pos(10, 10), ready_quest());
+  assert_eq!(claimable_quests(&quests, pos(10, 10), 1), vec![pos(10, 10)]);
+}
+
+#[test]
+fn test_reach_quest_times_out() {
+  let mut quest = Quest::new(Objective::Reach { offset: pos(4, 0), turns: 2 });
+  assert!(quest.tick());
+  assert!(!quest.tick());
+}
+
+#[test]
+fn test_reach_quest_ready_on_mark() {
+  let board = Buffer2D::new(Tile::default(), BOARD_RECT);
+  let mut quest = Quest::new(Objective::Reach { offset: pos(4, 0), turns: 2 });
+  quest.check(pos(10, 10), &board, pos(13, 10));
+  assert!(!quest.done);
+  quest.check(pos(10, 10), &board, pos(14, 10));
+  assert!(quest.done);
+  // a ready scroll doesn't time out
+  assert!(quest.tick());
+  assert!(quest.tick());
+}
+
+#[test]
+fn test_survive_quest_resets_on_damage() {
+  let mut quest = Quest::new(Objective::Survive { turns: 2, left: 2 });
+  quest.tick();
+  quest.credit_damage();
+  quest.tick();
+  assert!(!quest.done);
+  quest.tick();
+  assert!(quest.done);
+}
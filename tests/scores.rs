@@ -0,0 +1,153 @@
+use rl2025::*;
+
+fn record(score: i64, date: &str, class: &str, seed: u64) -> ScoreRecord {
+  ScoreRecord {
+    score,
+    won: score % 2 == 0,
+    seed,
+    date: date.to_string(),
+    class: class.to_string(),
+    mode: "Standard".to_string(),
+    turns: 100 - score,
+    tiles_placed: score * 2,
+    min_hp: score % 7,
+    regions_closed: score / 3,
+    bosses_slain: score % 15,
+  }
+}
+
+fn table() -> ScoreTable {
+  let mut table = ScoreTable::default();
+  table.add(record(40, "2026-01-03", "Ranger", 9));
+  table.add(record(75, "2025-12-30", "Builder", 3));
+  table.add(record(12, "2026-02-14", "Wanderer", 1 << 40));
+  table
+}
+
+#[test]
+fn test_round_trip() {
+  let table = table();
+  let text = table.serialize();
+  assert!(text.starts_with(&format!("version {}\n", SCORES_VERSION)));
+  let loaded = ScoreTable::deserialize(&text).unwrap();
+  assert_eq!(loaded.records, table.records);
+}
+
+#[test]
+fn test_old_records_are_standard_runs() {
+  let text = "version 1\nscore=5 won=false seed=2 date=2025-01-01 class=Wanderer turns=9 tiles=5 min_hp=1 regions=0 bosses=3\n";
+  let loaded = ScoreTable::deserialize(text).unwrap();
+  assert_eq!(loaded.records[0].mode, "Standard");
+  assert_eq!(loaded.records[0].bosses_slain, 3);
+}
+
+#[test]
+fn test_bad_tables_are_rejected() {
+  assert!(ScoreTable::deserialize("").is_err());
+  assert!(ScoreTable::deserialize("version 99\n").is_err());
+  assert!(ScoreTable::deserialize("version 1\nscore=5 won=true\n").is_err());
+  assert!(ScoreTable::deserialize("version 1\nscore=lots won=true seed=1 date=x class=y turns=1 tiles=1 min_hp=1 regions=1 bosses=1\n").is_err());
+}
+
+#[test]
+fn test_add_ranks_by_score() {
+  let mut table = table();
+  let scores: Vec<i64> = table.records.iter().map(|r| r.score).collect();
+  assert_eq!(scores, vec![75, 40, 12]);
+  assert_eq!(table.add(record(50, "2026-03-01", "Ranger", 4)), Some(1));
+  // ties go after the runs already there
+  assert_eq!(table.add(record(12, "2026-03-02", "Ranger", 5)), Some(4));
+}
+
+#[test]
+fn test_add_keeps_the_best() {
+  let mut table = ScoreTable::default();
+  for i in 0..MAX_SCORES as i64 {
+    assert_eq!(table.add(record(100 + i, "2026-01-01", "Ranger", 0)), Some(0));
+  }
+  assert_eq!(table.records.len(), MAX_SCORES);
+  assert_eq!(table.add(record(1, "2026-01-01", "Ranger", 0)), None);
+  assert_eq!(table.add(record(1000, "2026-01-01", "Ranger", 0)), Some(0));
+  assert_eq!(table.records.len(), MAX_SCORES);
+  assert_eq!(table.records.last().unwrap().score, 101);
+}
+
+#[test]
+fn test_sorted_by_each_field() {
+  let table = table();
+  let order = |field: ScoreField| -> Vec<i64> {
+    table.sorted_by(field).iter().map(|r| r.score).collect()
+  };
+  assert_eq!(order(ScoreField::Score), vec![75, 40, 12]);
+  // newest first
+  assert_eq!(order(ScoreField::Date), vec![12, 40, 75]);
+  // a to z
+  assert_eq!(order(ScoreField::Class), vec![75, 40, 12]);
+  // all standard, so score order is kept
+  assert_eq!(order(ScoreField::Mode), vec![75, 40, 12]);
+  // most first
+  assert_eq!(order(ScoreField::Turns), vec![12, 40, 75]);
+  assert_eq!(order(ScoreField::Tiles), vec![75, 40, 12]);
+  // ties keep score order
+  assert_eq!(order(ScoreField::MinHp), vec![75, 40, 12]);
+  assert_eq!(order(ScoreField::Regions), vec![75, 40, 12]);
+  assert_eq!(order(ScoreField::Bosses), vec![12, 40, 75]);
+  // lowest seed first
+  assert_eq!(order(ScoreField::Seed), vec![75, 40, 12]);
+}
+
+#[test]
+fn test_show_marks_defeats() {
+  let r = record(75, "2025-12-30", "Builder", 0xabcdef);
+  assert_eq!(ScoreField::Score.show(&r), "75*");
+  assert_eq!(ScoreField::Seed.show(&r), "00abcdef");
+}
+
+#[test]
+fn test_date_string() {
+  assert_eq!(date_string(0.), "1970-01-01");
+  assert_eq!(date_string(951782400.), "2000-02-29");
+  assert_eq!(date_string(1790000000.5), "2026-09-21");
+}
+
+fn endless(score: i64) -> ScoreRecord {
+  ScoreRecord { mode: "Endless".to_string(), ..record(score, "2026-04-01", "Ranger", 0) }
+}
+
+#[test]
+fn test_modes_rank_separately() {
+  let mut table = table();
+  // a long survival doesn't outrank standard runs, or count against them
+  assert_eq!(table.add(endless(5000)), Some(0));
+  assert_eq!(table.add(endless(30)), Some(1));
+  assert_eq!(table.add(record(20, "2026-03-01", "Ranger", 4)), Some(2));
+  let scores: Vec<i64> = table.sorted_by(ScoreField::Score).iter().map(|r| r.score).collect();
+  assert_eq!(scores, vec![75, 40, 20, 12, 5000, 30]);
+  let text = table.serialize();
+  assert_eq!(ScoreTable::deserialize(&text).unwrap().records, table.records);
+}
+
+#[test]
+fn test_each_mode_keeps_its_best() {
+  let mut table = ScoreTable::default();
+  for i in 0..MAX_SCORES as i64 {
+    table.add(record(100 + i, "2026-01-01", "Ranger", 0));
+  }
+  assert_eq!(table.add(endless(1)), Some(0));
+  for i in 0..MAX_SCORES as i64 {
+    table.add(endless(10 + i));
+  }
+  assert_eq!(table.records.len(), 2 * MAX_SCORES);
+  assert!(table.records.iter().all(|r| r.score != 1));
+  assert_eq!(table.add(record(1, "2026-01-01", "Ranger", 0)), None);
+}
+
+#[test]
+fn test_defeats_score_without_bosses() {
+  let score = ScoreRecord::compute_score(false, 30, 0, 2, 120, 0);
+  assert!(score > 0, "scored {}", score);
+  assert!(ScoreRecord::compute_score(false, 30, 0, 2, 120, 1) > score);
+  assert!(ScoreRecord::compute_score(false, 30, 0, 3, 120, 0) > score);
+  assert!(ScoreRecord::compute_score(false, 30, 0, 2, 200, 0) > score);
+  assert_eq!(ScoreRecord::compute_score(true, 30, 4, 2, 120, 0), 120);
+}
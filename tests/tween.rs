@@ -0,0 +1,89 @@
+use rl2025::*;
+use rl2025::footguns::Ref;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+const CURVES: [Curve; 6] = [
+  Curve::Linear, Curve::Quad, Curve::Cubic, Curve::Back, Curve::Elastic, Curve::Bounce,
+];
+
+fn close(a: f32, b: f32) -> bool {
+  (a - b).abs() < 1e-4
+}
+
+#[test]
+fn test_eases_start_and_end() {
+  for c in CURVES {
+    for ease in [Ease::In(c), Ease::Out(c), Ease::InOut(c)] {
+      assert!(close(ease.apply(0.), 0.), "{:?} starts at {}", ease, ease.apply(0.));
+      assert!(close(ease.apply(1.), 1.), "{:?} ends at {}", ease, ease.apply(1.));
+    }
+  }
+}
+
+#[test]
+fn test_ease_shapes() {
+  assert!(Ease::In(Curve::Quad).apply(0.5) < 0.5);
+  assert!(Ease::Out(Curve::Quad).apply(0.5) > 0.5);
+  assert!(close(Ease::InOut(Curve::Cubic).apply(0.5), 0.5));
+  // back dips below the start before heading out
+  assert!(Ease::In(Curve::Back).apply(0.2) < 0.);
+  assert!(Ease::Out(Curve::Back).apply(0.8) > 1.);
+}
+
+#[test]
+fn test_tween_writes_field() {
+  let target = Ref::new((0f32, Vec2::ZERO));
+  let mut t = tween(target.clone(), |v| &mut v.1, Vec2::ZERO, vec2(4., 2.), 2., Ease::LINEAR);
+  assert!(t.run(1.));
+  assert_eq!(target.peek().1, vec2(2., 1.));
+  assert!(!t.run(5.));
+  assert_eq!(target.peek().1, vec2(4., 2.));
+}
+
+#[test]
+fn test_sequence_finishes_each_step_once() {
+  let target = Ref::new(0f32);
+  let calls = Rc::new(RefCell::new(0));
+  let counter = calls.clone();
+  let mut t = Tween::sequence(vec![
+    tween(target.clone(), |v| v, 0., 1., 1., Ease::LINEAR),
+    Tween::call(move || *counter.borrow_mut() += 1),
+    tween(target.clone(), |v| v, 1., 3., 1., Ease::LINEAR),
+  ]);
+  assert_eq!(t.duration, 2.);
+  assert!(t.run(0.5));
+  assert_eq!(*target.peek(), 0.5);
+  assert_eq!(*calls.borrow(), 0);
+  // a coarse step still passes through the call
+  assert!(t.run(1.5));
+  assert_eq!(*target.peek(), 2.);
+  assert!(!t.run(2.5));
+  assert_eq!(*target.peek(), 3.);
+  assert_eq!(*calls.borrow(), 1);
+}
+
+#[test]
+fn test_parallel_lasts_as_long_as_longest() {
+  let target = Ref::new(Color::new(0., 0., 0., 0.));
+  let mut t = tween(target.clone(), |c| &mut c.r, 0., 1., 1., Ease::LINEAR)
+    .with(tween(target.clone(), |c| &mut c.a, 0., 1., 2., Ease::LINEAR));
+  assert_eq!(t.duration, 2.);
+  assert!(t.run(1.));
+  assert_eq!(target.peek().r, 1.);
+  assert_eq!(target.peek().a, 0.5);
+  assert!(!t.run(2.));
+  assert_eq!(target.peek().a, 1.);
+}
+
+#[test]
+fn test_queue_runs_tweens() {
+  let target = Ref::new(0f32);
+  let mut q = AnimationQueue::starting_at(0.);
+  q.append_tween(tween(target.clone(), |v| v, 0., 1., 1., Ease::LINEAR));
+  q.advance(0.25);
+  assert_eq!(*target.peek(), 0.25);
+  q.finish_all();
+  assert_eq!(*target.peek(), 1.);
+  assert_eq!(q.len(), 0);
+}
@@ -0,0 +1,30 @@
+// named text files in local storage, see src/storage.rs
+const STORAGE_PREFIX = "tilers_adventure_";
+
+function storage_key(ptr, len) {
+    const bytes = new Uint8Array(wasm_memory.buffer, ptr, len);
+    return STORAGE_PREFIX + new TextDecoder().decode(bytes);
+}
+
+function storage_bytes(key) {
+    const text = window.localStorage.getItem(key) || "";
+    return new TextEncoder().encode(text);
+}
+
+miniquad_add_plugin({
+    name: "storage",
+    version: 1,
+    register_plugin: function (importObject) {
+        importObject.env.storage_len = function (name, name_len) {
+            return storage_bytes(storage_key(name, name_len)).length;
+        };
+        importObject.env.storage_read = function (name, name_len, ptr, len) {
+            const bytes = storage_bytes(storage_key(name, name_len)).subarray(0, len);
+            new Uint8Array(wasm_memory.buffer, ptr, len).set(bytes);
+        };
+        importObject.env.storage_write = function (name, name_len, ptr, len) {
+            const bytes = new Uint8Array(wasm_memory.buffer, ptr, len);
+            window.localStorage.setItem(storage_key(name, name_len), new TextDecoder().decode(bytes));
+        };
+    },
+});